@@ -0,0 +1,36 @@
+//! Times `apply_match_results` on generated seasons, with and without reporting every update,
+//! next to the name keyed `apply_match_results_inspect` it is meant to outrun.
+//!
+//! Run with `cargo bench --bench apply_match_results`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use elo::fixtures::{generate_season, season_configuration};
+use elo::{apply_match_results, apply_match_results_inspect, apply_match_results_inspect_updates, EloError, GamesPlayed};
+
+const TEAMS: usize = 300;
+const SEASON_LENGTHS: [usize; 2] = [5_000, 50_000];
+
+fn bench_apply_match_results(c: &mut Criterion) {
+    let config = season_configuration();
+    let mut group = c.benchmark_group("apply_match_results");
+    group.sample_size(20);
+
+    for matches in SEASON_LENGTHS {
+        let season = generate_season(TEAMS, matches, 0);
+        group.throughput(Throughput::Elements(matches as u64));
+        group.bench_with_input(BenchmarkId::new("interned", matches), &season, |b, season| {
+            b.iter(|| apply_match_results(&season.matches, &season.standings, &config).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("interned_inspect", matches), &season, |b, season| {
+            b.iter(|| apply_match_results_inspect_updates(&season.matches, &season.standings, &GamesPlayed::new(), &config, |_| Ok::<(), EloError>(())).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("name_keyed", matches), &season, |b, season| {
+            b.iter(|| apply_match_results_inspect(&season.matches, &season.standings, &config, |_, _| Ok::<(), EloError>(())).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_apply_match_results);
+criterion_main!(benches);
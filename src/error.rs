@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong while reading inputs or applying matches.
+#[derive(Debug)]
+pub enum EloError {
+    /// An input file does not exist.
+    FileNotFound(PathBuf),
+    /// An input file exists but could not be read.
+    Io { path: PathBuf, source: io::Error },
+    /// An input file could be read but its contents are malformed.
+    ParseError { path: PathBuf, source: Box<dyn Error + Send + Sync> },
+    /// A team needed for the operation is not in the standings.
+    MissingTeam(String),
+    /// The configuration cannot be used, see `Configuration::validate`.
+    InvalidConfig(String),
+    /// No k bracket covers the rating a match was played at.
+    NoKBracket(f32),
+    /// A match names the same team as both winner and loser.
+    SelfMatch(String),
+    /// Matches were given for empty standings without a configured `default_rating`.
+    NoTeams,
+    /// Adjusting ratings gave an infinite or NaN rating, described by the message.
+    NonFiniteRating(String),
+    /// Applying the match at `index` of a list of matches failed with `source`.
+    InMatch { index: usize, result: Box<crate::MatchResult>, source: Box<EloError> },
+}
+
+impl EloError {
+    /// Wraps the error with the match it happened in and that match's index in the list.
+    pub fn in_match(self, index: usize, result: &crate::MatchResult) -> EloError {
+        EloError::InMatch { index, result: Box::new(result.clone()), source: Box::new(self) }
+    }
+}
+
+impl fmt::Display for EloError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EloError::FileNotFound(path) => write!(f, "'{}' does not exist", path.display()),
+            EloError::Io { path, source } => write!(f, "could not read '{}': {}", path.display(), source),
+            EloError::ParseError { path, source } => write!(f, "could not parse '{}': {}", path.display(), source),
+            EloError::MissingTeam(team) => write!(f, "team '{}' is not in the standings", team),
+            EloError::InvalidConfig(message) => write!(f, "{}", message),
+            EloError::NoKBracket(rating) => write!(f, "no k bracket covers a rating of {}", rating),
+            EloError::SelfMatch(team) => write!(f, "team '{}' cannot play a match against itself", team),
+            EloError::NoTeams => write!(f, "no teams defined, the standings are empty and the config does not set a default_rating"),
+            EloError::NonFiniteRating(message) => write!(f, "{} did not give a finite rating", message),
+            EloError::InMatch { index, result, source } => write!(f, "match {} ({}): {}", index, result, source),
+        }
+    }
+}
+
+impl Error for EloError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EloError::Io { source, .. } => Some(source),
+            EloError::ParseError { source, .. } => Some(source.as_ref()),
+            EloError::InMatch { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<crate::InvalidConfiguration> for EloError {
+    fn from(error: crate::InvalidConfiguration) -> Self {
+        EloError::InvalidConfig(error.to_string())
+    }
+}
@@ -0,0 +1,81 @@
+//! Synthetic seasons for benchmarks and tests that need more matches than are worth writing out
+//! by hand.
+
+use crate::{get_expected_probabilities, Configuration, MatchResult, SeriesKind, Standings};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Generated teams and matches, with every team starting from 1500.
+pub struct Season {
+    pub standings: Standings,
+    pub matches: Vec<MatchResult>,
+}
+
+/// The name of team `index` in a generated season. The names are long on purpose, so the cost of
+/// hashing and cloning team names shows up in benchmarks as it would with real names.
+pub fn team_name(index: usize) -> String {
+    format!("Professional Esports Organisation {}", index)
+}
+
+/// A season of `matches` matches between `teams` teams drawn from a ChaCha RNG seeded with
+/// `seed`, so the same arguments always give the same season.
+///
+/// Every team has a hidden strength between 1200 and 1800 and the winner of each match is drawn
+/// with the expected probability of those strengths, so the ratings have something to converge
+/// to. Most matches are Bo1s, with some Bo3s and Bo5s. Needs at least two teams.
+pub fn generate_season(teams: usize, matches: usize, seed: u64) -> Season {
+    assert!(teams >= 2, "a season needs at least two teams, got {}", teams);
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let names: Vec<String> = (0..teams).map(team_name).collect();
+    let strengths: Vec<f32> = (0..teams).map(|_| rng.gen_range(1200f32..1800f32)).collect();
+    let matches = (0..matches)
+        .map(|_| {
+            let team_a = rng.gen_range(0..teams);
+            let team_b = (team_a + rng.gen_range(1..teams)) % teams;
+            let (a_wins, _) = get_expected_probabilities(strengths[team_a], strengths[team_b]);
+            let (winner, loser) = if rng.gen::<f32>() < a_wins { (team_a, team_b) } else { (team_b, team_a) };
+            let series = match rng.gen_range(0..10) {
+                0..=6 => SeriesKind::Bo1,
+                7 | 8 => SeriesKind::Bo3,
+                _ => SeriesKind::Bo5,
+            };
+            MatchResult { winner: names[winner].clone(), loser: names[loser].clone(), series, ..Default::default() }
+        })
+        .collect();
+
+    Season {
+        standings: names.into_iter().map(|name| (name, 1500f32)).collect(),
+        matches,
+    }
+}
+
+/// A plain configuration for generated seasons, two k brackets and the usual series scores.
+pub fn season_configuration() -> Configuration {
+    serde_json::from_value(serde_json::json!({
+        "bo1_score": 1,
+        "bo3_score": 1.25,
+        "bo5_score": 1.5,
+        "k_brackets": [{"start": 0, "k": 32}, {"start": 1600, "k": 24}],
+    })).expect("the season configuration is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seasons_are_reproducible() {
+        let first = generate_season(20, 500, 3);
+        let second = generate_season(20, 500, 3);
+        assert_eq!(first.standings, second.standings);
+        assert_eq!(first.matches.len(), 500);
+        for (a, b) in first.matches.iter().zip(second.matches.iter()) {
+            assert_eq!((&a.winner, &a.loser, a.series), (&b.winner, &b.loser, b.series));
+        }
+        assert!(first.matches.iter().all(|result| result.winner != result.loser && first.standings.contains_key(&result.winner)));
+
+        let other = generate_season(20, 500, 4);
+        assert!(first.matches.iter().zip(other.matches.iter()).any(|(a, b)| a.winner != b.winner));
+    }
+}
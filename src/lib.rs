@@ -0,0 +1,5137 @@
+//! ELO ratings for teams that play best of N series.
+//!
+//! The numeric core, `get_expected_probabilities`, `adjust_ratings` and `scaling_for_rating`,
+//! works on plain ratings. `update_ratings` rates one series with a `Configuration`, and
+//! `apply_match_results` rates a whole list of `MatchResult`s against `Standings`.
+
+mod error;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+pub mod repl;
+pub mod schema;
+pub mod simulate;
+
+pub use error::EloError;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub type Standings = HashMap<String, f32>;
+pub type GamesPlayed = HashMap<String, u32>;
+/// Matches won by each team, draws count for neither team.
+pub type Wins = HashMap<String, u32>;
+/// Names to show for teams in place of the keys they are stored and matched under.
+pub type DisplayNames = HashMap<String, String>;
+/// The division, or any other group, each team is tagged with in the standings.
+pub type Divisions = HashMap<String, String>;
+
+/// The name to show for `team`, its display name if it has one.
+pub fn display_name<'a>(team: &'a str, display_names: &'a DisplayNames) -> &'a str {
+    display_names.get(team).map_or(team, String::as_str)
+}
+
+/// Reads from any casing of `"bo3"`, as well as `{"Custom": games}`, and is written as `"Bo3"` so
+/// reports read back the same.
+#[derive(std::marker::Copy, std::clone::Clone, Default, Debug, PartialEq, Eq, Hash)]
+pub enum SeriesKind {
+    #[default]
+    Bo1,
+    Bo3,
+    Bo5,
+    Bo7,
+    /// A series of any other number of games.
+    Custom(u32),
+}
+
+impl FromStr for SeriesKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bo1" => Ok(SeriesKind::Bo1),
+            "bo3" => Ok(SeriesKind::Bo3),
+            "bo5" => Ok(SeriesKind::Bo5),
+            "bo7" => Ok(SeriesKind::Bo7),
+            other => other
+                .strip_prefix("bo")
+                .and_then(|games| games.parse().ok())
+                .filter(|games| *games > 0)
+                .map(SeriesKind::Custom)
+                .ok_or_else(|| format!("invalid series kind `{}`, expected bo followed by the number of games", s)),
+        }
+    }
+}
+
+impl serde::Serialize for SeriesKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            // Written as "Bo3" these would read back as the named kinds, which `custom_scores` can
+            // score differently.
+            SeriesKind::Custom(games @ (1 | 3 | 5 | 7)) => serializer.serialize_newtype_variant("SeriesKind", 4, "Custom", games),
+            SeriesKind::Custom(games) => serializer.collect_str(&format_args!("Bo{}", games)),
+            named => serializer.collect_str(&format_args!("{:?}", named)),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SeriesKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SeriesKindVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for SeriesKindVisitor {
+            type Value = SeriesKind;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a series kind like \"Bo3\" or {{\"Custom\": games}}")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<SeriesKind, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<SeriesKind, A::Error> {
+                use serde::de::Error;
+
+                let variant: String = map.next_key()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+                if !variant.eq_ignore_ascii_case("custom") {
+                    return Err(A::Error::unknown_variant(&variant, &["Custom"]));
+                }
+                match map.next_value()? {
+                    0 => Err(A::Error::custom("a custom series must have at least one game")),
+                    games => Ok(SeriesKind::Custom(games)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(SeriesKindVisitor)
+    }
+}
+
+#[derive(serde::Deserialize, std::clone::Clone, Debug)]
+pub struct MatchResult {
+    pub winner: String,
+    pub loser: String,
+    pub series: SeriesKind,
+    #[serde(default)]
+    pub draw: bool,
+    #[serde(default)]
+    pub winner_games: Option<u32>,
+    #[serde(default)]
+    pub loser_games: Option<u32>,
+    /// Name of the team that played at home, if either did.
+    #[serde(default)]
+    pub home: Option<String>,
+    /// Multiplies k for this match, 1 when absent.
+    #[serde(default)]
+    pub importance: Option<f32>,
+    /// ISO-8601 date the match was played on, such as `2021-06-12`.
+    #[serde(default)]
+    pub date: Option<String>,
+    /// Unrated matches, such as exhibitions, are kept for the record but never change ratings.
+    #[serde(default = "default_rated")]
+    pub rated: bool,
+}
+
+impl fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = if self.draw { "drew with" } else { "beat" };
+        write!(f, "{} {} {} in a {:?}", self.winner, verb, self.loser, self.series)?;
+        if let Some(date) = self.date.as_deref() {
+            write!(f, " on {}", date)?;
+        }
+        Ok(())
+    }
+}
+
+fn default_rated() -> bool {
+    true
+}
+
+impl Default for MatchResult {
+    fn default() -> Self {
+        MatchResult {
+            winner: String::new(),
+            loser: String::new(),
+            series: SeriesKind::default(),
+            draw: false,
+            winner_games: None,
+            loser_games: None,
+            home: None,
+            importance: None,
+            date: None,
+            rated: true,
+        }
+    }
+}
+
+/// A match between more than two teams where only the finishing order counts.
+#[derive(serde::Deserialize)]
+pub struct FreeForAll {
+    /// Every team that played, from first place to last.
+    pub placements: Vec<String>,
+    /// Unrated free-for-alls never change ratings, like unrated matches.
+    #[serde(default = "default_rated")]
+    pub rated: bool,
+}
+
+impl Default for FreeForAll {
+    fn default() -> Self {
+        FreeForAll { placements: Vec::new(), rated: true }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, std::marker::Copy, std::clone::Clone)]
+pub struct KBracket {
+    pub start: u32,
+    pub k: f32
+}
+
+/// Which rating picks the k bracket of a match.
+#[derive(serde::Deserialize, serde::Serialize, Copy, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KSelection {
+    /// The average of the two ratings.
+    #[default]
+    #[serde(alias = "combined")]
+    Average,
+    Winner,
+    Loser,
+    /// The absolute difference between the two ratings.
+    Difference,
+}
+
+impl KSelection {
+    /// The rating used to look up the k bracket of a match between the two teams.
+    pub fn bracket_rating(self, winner_rating: f32, loser_rating: f32) -> f32 {
+        match self {
+            KSelection::Average => combine_ratings(winner_rating, loser_rating),
+            KSelection::Winner => winner_rating,
+            KSelection::Loser => loser_rating,
+            KSelection::Difference => (winner_rating - loser_rating).abs(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, std::clone::Clone)]
+pub struct Configuration {
+    pub bo1_score: f32,
+    pub bo3_score: f32,
+    pub bo5_score: f32,
+    /// Falls back to `bo5_score` when absent so older configs keep working.
+    #[serde(default)]
+    pub bo7_score: Option<f32>,
+    /// Scores for `Custom` series keyed by number of games.
+    #[serde(default)]
+    pub custom_scores: HashMap<u32, f32>,
+    /// Scores for series won by a given game score, such as `"3-0"`, keyed by series kind. Results
+    /// without game counts or with a game score not listed use the flat series score.
+    #[serde(default)]
+    pub series_margin_scores: HashMap<SeriesKind, HashMap<String, f32>>,
+    pub k_brackets: Vec<KBracket>,
+    /// Also accepted as `bracket_basis`.
+    #[serde(default, alias = "bracket_basis")]
+    pub k_selection: KSelection,
+    /// Interpolates k between brackets rather than stepping at each `start`, see
+    /// `interpolated_scaling_for_rating`.
+    #[serde(default)]
+    pub bracket_interpolation: bool,
+    /// Rating of teams that are not in the standings yet, see `Configuration::default_rating`.
+    #[serde(default)]
+    pub default_rating: Option<f32>,
+    /// No rating is ever adjusted below this value when set.
+    #[serde(default)]
+    pub rating_floor: Option<f32>,
+    /// Teams rated above `rating_ceiling` only gain `ceiling_compression` times the rating they
+    /// would otherwise gain. Losses are not compressed.
+    #[serde(default)]
+    pub rating_ceiling: Option<f32>,
+    #[serde(default)]
+    pub ceiling_compression: Option<f32>,
+    /// No single match changes a rating by more than this when set.
+    #[serde(default)]
+    pub max_delta_per_match: Option<f32>,
+    /// Replaces the bracket k for the named teams. Each team in a match is adjusted with its own
+    /// k, so an override only affects the rating of the team it names.
+    #[serde(default)]
+    pub team_k_overrides: HashMap<String, f32>,
+    /// Maps alternate names of a team, such as its name before a rebrand, to its canonical name so
+    /// old match files can keep their original names while ratings accrue to one team.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Placeholder teams such as `BYE`. Matches involving one are skipped as if they were unrated.
+    #[serde(default)]
+    pub ignored_teams: HashSet<String>,
+    /// Teams that have played fewer than `provisional_games` use `provisional_k` instead of
+    /// the bracket k. Team overrides still take precedence.
+    #[serde(default)]
+    pub provisional_games: u32,
+    #[serde(default)]
+    pub provisional_k: Option<f32>,
+    /// Teams that have played fewer games are listed apart from the ranked teams on leaderboards.
+    #[serde(default)]
+    pub leaderboard_min_games: u32,
+    /// Rating bonus the home team plays with when a match names one.
+    #[serde(default)]
+    pub home_advantage: Option<f32>,
+    /// Scales k up for surprising results, see `Configuration::upset_multiplier`.
+    #[serde(default)]
+    pub upset_factor: Option<f32>,
+    /// Scales k down for teams that keep meeting in the same run, see
+    /// `Configuration::repeat_multiplier`.
+    #[serde(default)]
+    pub repeat_matchup_decay: Option<f32>,
+    #[serde(default = "default_elo_base")]
+    pub elo_base: f32,
+    #[serde(default = "default_elo_divisor")]
+    pub elo_divisor: f32,
+}
+
+/// The rating teams start at when the configuration does not set `default_rating`.
+pub const DEFAULT_RATING: f32 = 1500f32;
+
+fn default_elo_base() -> f32 {
+    EloScale::default().base
+}
+
+fn default_elo_divisor() -> f32 {
+    EloScale::default().divisor
+}
+
+/// Every problem found while validating a `Configuration`.
+#[derive(Debug)]
+pub struct InvalidConfiguration {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for InvalidConfiguration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid configuration: {}", self.problems.join("; "))
+    }
+}
+
+impl Error for InvalidConfiguration {}
+
+/// Series scores above this are almost certainly a typo.
+const MAX_SERIES_SCORE: f32 = 10f32;
+
+impl Configuration {
+    /// The rating teams that are not in the standings yet start at, 1500 unless configured.
+    pub fn default_rating(&self) -> f32 {
+        self.default_rating.unwrap_or(DEFAULT_RATING)
+    }
+
+    pub fn scale(&self) -> EloScale {
+        EloScale { base: self.elo_base, divisor: self.elo_divisor }
+    }
+
+    pub fn expected_probabilities(&self, rating1: f32, rating2: f32) -> (f32, f32) {
+        get_expected_probabilities_with_scale(rating1, rating2, self.scale())
+    }
+
+    /// Scales a positive `delta` for a team rated `rating` by `ceiling_compression` when the
+    /// rating is above `rating_ceiling`. Any other delta is returned unchanged.
+    pub fn compress_gain(&self, rating: f32, delta: f32) -> f32 {
+        match (self.rating_ceiling, self.ceiling_compression) {
+            (Some(ceiling), Some(compression)) if rating > ceiling && delta > 0f32 => delta * compression,
+            _ => delta,
+        }
+    }
+
+    /// The name ratings of `team` are stored under, resolving it through `aliases`.
+    pub fn canonical_team<'a>(&'a self, team: &'a str) -> &'a str {
+        self.aliases.get(team).map_or(team, String::as_str)
+    }
+
+    /// Whether the winner or loser of `result` is one of `ignored_teams`.
+    pub fn involves_ignored_team(&self, result: &MatchResult) -> bool {
+        let ignored = |team: &str| self.ignored_teams.contains(self.canonical_team(team));
+        ignored(&result.winner) || ignored(&result.loser)
+    }
+
+    /// Whether `result` changes any ratings, false for unrated matches and matches against an
+    /// ignored team.
+    pub fn counts_match(&self, result: &MatchResult) -> bool {
+        result.rated && !self.involves_ignored_team(result)
+    }
+
+    /// The rating change a team rated `rating` actually gets for a match that would change it by
+    /// `delta`. The change is clamped to `max_delta_per_match` and then compressed by
+    /// `compress_gain`. The rating floor is applied separately.
+    pub fn limit_delta(&self, rating: f32, delta: f32) -> f32 {
+        let delta = match self.max_delta_per_match {
+            Some(max_delta) => delta.clamp(-max_delta, max_delta),
+            None => delta,
+        };
+        self.compress_gain(rating, delta)
+    }
+
+    /// The rating a team rated `rating` ends a match on when the rating system moved it to
+    /// `new_rating`, after `limit_delta` and the `rating_floor`.
+    fn bound_rating(&self, rating: f32, new_rating: f32) -> f32 {
+        let bounded = rating + self.limit_delta(rating, new_rating - rating);
+        match self.rating_floor {
+            Some(rating_floor) => bounded.max(rating_floor),
+            None => bounded,
+        }
+    }
+
+    /// The multiplier applied to k when a team expected to score `expected` scored `outcome`,
+    /// `1 + upset_factor * |outcome - expected|`. Upsets are far from what was expected and move
+    /// ratings more, while a heavy favourite winning barely changes k. Always 1 without an
+    /// `upset_factor`.
+    pub fn upset_multiplier(&self, expected: f32, outcome: f32) -> f32 {
+        1f32 + self.upset_factor.unwrap_or(0f32) * (outcome - expected).abs()
+    }
+
+    /// The multiplier applied to k when two teams have already met `previous_meetings` times in
+    /// the current run, `repeat_matchup_decay ^ previous_meetings`. Always 1 without a decay, so
+    /// a team cannot farm rating by beating the same opponent over and over.
+    pub fn repeat_multiplier(&self, previous_meetings: u32) -> f32 {
+        self.repeat_matchup_decay.map_or(1f32, |decay| decay.powi(previous_meetings as i32))
+    }
+
+    /// The bracket ratings below the lowest bracket fall back to, `None` without brackets.
+    fn lowest_bracket(&self) -> Option<&KBracket> {
+        self.k_brackets.iter().rev().min_by_key(|bracket| bracket.start)
+    }
+
+    /// Settings that are valid but probably not what was meant. The only one so far is a lowest k
+    /// bracket starting above 0, ratings below it use the k of that bracket.
+    pub fn warnings(&self) -> Vec<String> {
+        match self.lowest_bracket() {
+            Some(bracket) if bracket.start > 0 => vec![format!(
+                "the lowest k bracket starts at {}, ratings below it use its k of {}",
+                bracket.start, bracket.k
+            )],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The score `series_margin_scores` gives a series won `winner_games` to `loser_games`, if it
+    /// lists that game score for the series kind.
+    pub fn margin_score(&self, series: SeriesKind, winner_games: Option<u32>, loser_games: Option<u32>) -> Option<f32> {
+        let games = (winner_games?, loser_games?);
+        self.series_margin_scores
+            .get(&series)?
+            .iter()
+            .find(|(margin, _)| parse_game_score(margin) == Some(games))
+            .map(|(_, score)| *score)
+    }
+
+    /// Checks that there are k brackets with positive k values and distinct starts and that the
+    /// series scores are in `(0, 10]`, reporting every problem found rather than just the first.
+    pub fn validate(&self) -> Result<(), InvalidConfiguration> {
+        let mut problems = Vec::new();
+
+        if self.k_brackets.is_empty() {
+            problems.push("k_brackets must not be empty".to_string());
+        }
+
+        for bracket in self.k_brackets.iter() {
+            if !(bracket.k > 0f32 && bracket.k.is_finite()) {
+                problems.push(format!("k of bracket starting at {} must be positive, got {}", bracket.start, bracket.k));
+            }
+        }
+        let mut starts: Vec<u32> = self.k_brackets.iter().map(|bracket| bracket.start).collect();
+        starts.sort_unstable();
+        let mut duplicate_starts: Vec<u32> = starts.windows(2).filter(|pair| pair[0] == pair[1]).map(|pair| pair[0]).collect();
+        duplicate_starts.dedup();
+        for start in duplicate_starts {
+            problems.push(format!("more than one k bracket starts at {}", start));
+        }
+
+        let mut series_scores = vec![("bo1_score", self.bo1_score), ("bo3_score", self.bo3_score), ("bo5_score", self.bo5_score)];
+        if let Some(bo7_score) = self.bo7_score {
+            series_scores.push(("bo7_score", bo7_score));
+        }
+        for (name, score) in series_scores.iter() {
+            if !(*score > 0f32 && *score <= MAX_SERIES_SCORE) {
+                problems.push(format!("{} must be in (0, {}], got {}", name, MAX_SERIES_SCORE, score));
+            }
+        }
+
+        let optional_values = [
+            ("default_rating", self.default_rating),
+            ("rating_floor", self.rating_floor),
+            ("rating_ceiling", self.rating_ceiling),
+            ("provisional_k", self.provisional_k),
+            ("home_advantage", self.home_advantage),
+        ];
+        for (name, value) in optional_values.iter() {
+            if let Some(value) = value {
+                if !value.is_finite() {
+                    problems.push(format!("{} must be a finite number, got {}", name, value));
+                }
+            }
+        }
+
+        let mut overridden_teams: Vec<&String> = self.team_k_overrides.keys().collect();
+        overridden_teams.sort();
+        for team in overridden_teams {
+            let k = self.team_k_overrides[team];
+            if !(k > 0f32 && k.is_finite()) {
+                problems.push(format!("k override for team '{}' must be positive, got {}", team, k));
+            }
+        }
+
+        if let Some(compression) = self.ceiling_compression {
+            if !(0f32..=1f32).contains(&compression) {
+                problems.push(format!("ceiling_compression must be in [0, 1], got {}", compression));
+            }
+        }
+
+        if let Some(max_delta) = self.max_delta_per_match {
+            if max_delta <= 0f32 || max_delta.is_nan() {
+                problems.push(format!("max_delta_per_match must be positive, got {}", max_delta));
+            }
+        }
+
+        if let Some(upset_factor) = self.upset_factor {
+            if !(upset_factor >= 0f32 && upset_factor.is_finite()) {
+                problems.push(format!("upset_factor must not be negative, got {}", upset_factor));
+            }
+        }
+
+        if let Some(decay) = self.repeat_matchup_decay {
+            if !(decay > 0f32 && decay <= 1f32) {
+                problems.push(format!("repeat_matchup_decay must be in (0, 1], got {}", decay));
+            }
+        }
+
+        if !(self.elo_base > 1f32 && self.elo_base.is_finite()) {
+            problems.push(format!("elo_base must be greater than 1, got {}", self.elo_base));
+        }
+        if !(self.elo_divisor > 0f32 && self.elo_divisor.is_finite()) {
+            problems.push(format!("elo_divisor must be positive, got {}", self.elo_divisor));
+        }
+
+        let mut margin_series: Vec<&SeriesKind> = self.series_margin_scores.keys().collect();
+        margin_series.sort_by_key(|series| format!("{:?}", series));
+        for series in margin_series {
+            let mut margins: Vec<(&String, &f32)> = self.series_margin_scores[series].iter().collect();
+            margins.sort_by_key(|(margin, _)| *margin);
+            for (margin, score) in margins {
+                if parse_game_score(margin).is_none() {
+                    problems.push(format!("margin score key '{}' for {:?} must be a game score like 3-0", margin, series));
+                } else if !(*score > 0f32 && *score <= MAX_SERIES_SCORE) {
+                    problems.push(format!("margin score for {:?} {} must be in (0, {}], got {}", series, margin, MAX_SERIES_SCORE, score));
+                }
+            }
+        }
+
+        let mut custom_games: Vec<&u32> = self.custom_scores.keys().collect();
+        custom_games.sort();
+        for games in custom_games {
+            let score = self.custom_scores[games];
+            if !(score > 0f32 && score <= MAX_SERIES_SCORE) {
+                problems.push(format!("custom score for {} games must be in (0, {}], got {}", games, MAX_SERIES_SCORE, score));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidConfiguration { problems })
+        }
+    }
+}
+
+/// Splits a game score such as `3-1` into the games won by the winner and the loser.
+fn parse_game_score(score: &str) -> Option<(u32, u32)> {
+    let (winner_games, loser_games) = score.split_once('-')?;
+    Some((winner_games.trim().parse().ok()?, loser_games.trim().parse().ok()?))
+}
+
+pub fn get_series_win_weight_from_config(configuration: &Configuration) -> impl Fn(SeriesKind) -> f32 + '_ {
+    move |series| {
+        match series {
+            SeriesKind::Bo1 => configuration.bo1_score,
+            SeriesKind::Bo3 => configuration.bo3_score,
+            SeriesKind::Bo5 => configuration.bo5_score,
+            SeriesKind::Bo7 => configuration.bo7_score.unwrap_or(configuration.bo5_score),
+            SeriesKind::Custom(games) => custom_series_score(configuration, games),
+        }
+    }
+}
+
+/// The score for a custom series of `games` games.
+///
+/// Scores listed in `custom_scores` are used as is. Otherwise the named series kinds are used for
+/// 1, 3, 5 and 7 games, and any other length is extrapolated linearly from `bo1_score` and
+/// `bo5_score`, `bo1_score + (games - 1) / 4 * (bo5_score - bo1_score)`.
+fn custom_series_score(configuration: &Configuration, games: u32) -> f32 {
+    if let Some(score) = configuration.custom_scores.get(&games) {
+        return *score;
+    }
+
+    match games {
+        1 => configuration.bo1_score,
+        3 => configuration.bo3_score,
+        5 => configuration.bo5_score,
+        7 => configuration.bo7_score.unwrap_or(configuration.bo5_score),
+        _ => configuration.bo1_score + (games as f32 - 1f32) / 4f32 * (configuration.bo5_score - configuration.bo1_score),
+    }
+}
+
+/// The constants of the expected score formula, `1 / (1 + base ^ ((rating2 - rating1) / divisor))`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EloScale {
+    pub base: f32,
+    pub divisor: f32,
+}
+
+impl Default for EloScale {
+    /// The standard ELO constants, a 400 point gap means the stronger team is 10 times as likely to win.
+    fn default() -> Self {
+        EloScale { base: 10f32, divisor: 400f32 }
+    }
+}
+
+/// The expected scores of two teams against each other with the standard ELO constants. The two
+/// always add up to 1.
+///
+/// ```
+/// let (favourite, underdog) = elo::get_expected_probabilities(1600.0, 1200.0);
+/// assert!((favourite - 10.0 / 11.0).abs() < 1e-6);
+/// assert!((favourite + underdog - 1.0).abs() < 1e-6);
+/// ```
+pub fn get_expected_probabilities(rating1: f32, rating2: f32) -> (f32, f32) {
+    get_expected_probabilities_with_scale(rating1, rating2, EloScale::default())
+}
+
+/// The expected scores of two teams against each other with the constants of `scale`.
+///
+/// ```
+/// use elo::{get_expected_probabilities_with_scale, EloScale};
+///
+/// // With a divisor of 200 a 200 point gap is already worth 10 to 1.
+/// let scale = EloScale { base: 10.0, divisor: 200.0 };
+/// let (favourite, _) = get_expected_probabilities_with_scale(1400.0, 1200.0, scale);
+/// assert!((favourite - 10.0 / 11.0).abs() < 1e-6);
+/// ```
+pub fn get_expected_probabilities_with_scale(rating1: f32, rating2: f32, scale: EloScale) -> (f32, f32) {
+    let p1 = 1f32 / (1f32 + f32::powf(scale.base, (rating2 - rating1) / scale.divisor));
+    let p2 = 1f32 / (1f32 + f32::powf(scale.base, (rating1 - rating2) / scale.divisor));
+
+    (p1, p2)
+}
+
+/// The probability of a team rated `diff` above its opponent winning, with the standard ELO
+/// constants. A negative `diff` is an underdog.
+///
+/// ```
+/// assert!((elo::probability_for_rating_diff(400.0) - 10.0 / 11.0).abs() < 1e-6);
+/// assert_eq!(elo::probability_for_rating_diff(0.0), 0.5);
+/// ```
+pub fn probability_for_rating_diff(diff: f32) -> f32 {
+    probability_for_rating_diff_with_scale(diff, EloScale::default())
+}
+
+/// The probability of a team rated `diff` above its opponent winning, with the constants of `scale`.
+pub fn probability_for_rating_diff_with_scale(diff: f32, scale: EloScale) -> f32 {
+    get_expected_probabilities_with_scale(diff, 0f32, scale).0
+}
+
+/// The rating gap that gives a win probability of `p` with the standard ELO constants, the
+/// inverse of `probability_for_rating_diff`. Probabilities below 0.5 give a negative gap, 0 and 1
+/// give infinite gaps and anything outside `[0, 1]` gives NaN.
+///
+/// ```
+/// assert!((elo::rating_diff_for_probability(10.0 / 11.0) - 400.0).abs() < 1e-2);
+/// assert_eq!(elo::rating_diff_for_probability(0.5), 0.0);
+/// ```
+pub fn rating_diff_for_probability(p: f32) -> f32 {
+    rating_diff_for_probability_with_scale(p, EloScale::default())
+}
+
+/// The rating gap that gives a win probability of `p` with the constants of `scale`,
+/// `divisor * log_base(p / (1 - p))`.
+pub fn rating_diff_for_probability_with_scale(p: f32, scale: EloScale) -> f32 {
+    scale.divisor * (p / (1f32 - p)).log(scale.base)
+}
+
+/// The k of the bracket `rating` falls in, the one with the highest `start` at or below it.
+/// `None` when the rating is below every bracket.
+///
+/// Validated configurations never have two brackets with the same `start`, but when a list does
+/// the bracket listed last wins, here and everywhere else a bracket is looked up.
+///
+/// ```
+/// use elo::{scaling_for_rating, KBracket};
+///
+/// let brackets = [KBracket { start: 0, k: 40.0 }, KBracket { start: 2000, k: 20.0 }];
+/// assert_eq!(scaling_for_rating(1999.0, &brackets), Some(40.0));
+/// assert_eq!(scaling_for_rating(2000.0, &brackets), Some(20.0));
+/// assert_eq!(scaling_for_rating(-10.0, &brackets), None);
+/// ```
+pub fn scaling_for_rating(rating: f32, k_brackets: &[KBracket]) -> Option<f32> {
+    // `max_by_key` keeps the last of equal starts.
+    k_brackets
+        .iter()
+        .filter(|bracket| rating >= bracket.start as f32)
+        .max_by_key(|bracket| bracket.start)
+        .map(|bracket| bracket.k)
+}
+
+/// The k for `rating` interpolated linearly between the `start` of the bracket it falls in and the
+/// `start` of the next one, so k changes smoothly instead of jumping at every boundary. A rating
+/// exactly at a `start` gets that bracket's k. Below the first bracket k stays at the first
+/// bracket's k and from the last `start` up it stays at the last bracket's k. `None` without
+/// brackets.
+///
+/// ```
+/// use elo::{interpolated_scaling_for_rating, KBracket};
+///
+/// let brackets = [KBracket { start: 1000, k: 40.0 }, KBracket { start: 2000, k: 20.0 }];
+/// assert_eq!(interpolated_scaling_for_rating(1500.0, &brackets), Some(30.0));
+/// assert_eq!(interpolated_scaling_for_rating(500.0, &brackets), Some(40.0));
+/// assert_eq!(interpolated_scaling_for_rating(2500.0, &brackets), Some(20.0));
+/// ```
+pub fn interpolated_scaling_for_rating(rating: f32, k_brackets: &[KBracket]) -> Option<f32> {
+    let below = k_brackets.iter().filter(|bracket| rating >= bracket.start as f32).max_by_key(|bracket| bracket.start);
+    let above = k_brackets.iter().rev().filter(|bracket| rating < bracket.start as f32).min_by_key(|bracket| bracket.start);
+
+    match (below, above) {
+        (Some(below), Some(above)) => {
+            let fraction = (rating - below.start as f32) / (above.start - below.start) as f32;
+            Some(below.k + fraction * (above.k - below.k))
+        },
+        (Some(bracket), None) | (None, Some(bracket)) => Some(bracket.k),
+        (None, None) => None,
+    }
+}
+
+/// The average of two ratings.
+pub fn combine_ratings(rating1: f32, rating2: f32) -> f32 {
+    (rating1 + rating2) / 2f32
+}
+
+/// The bracket k for the average of the two ratings, which is what `KSelection::Average` uses.
+pub fn scaling_for_combined_rating(rating1: f32, rating2: f32, k_brackets: &[KBracket]) -> Option<f32> {
+    let bracket_rating = combine_ratings(rating1, rating2);
+    scaling_for_rating(bracket_rating, k_brackets)
+}
+
+/// The old name of `scaling_for_combined_rating`, kept so existing callers still build.
+#[deprecated(note = "renamed to `scaling_for_combined_rating`, it uses the average of the ratings")]
+pub fn scaling_for_rating_difference(rating1: f32, rating2: f32, k_brackets: &[KBracket]) -> Option<f32> {
+    scaling_for_combined_rating(rating1, rating2, k_brackets)
+}
+
+/// Scales k by how decisive a series was.
+///
+/// The multiplier is `1 + (winner_games - loser_games - 1) / (winner_games + loser_games)`, so a
+/// series won by a single game (2-1, 3-2) leaves k unchanged while a 2-0 sweep gives 1.5 and a
+/// 3-0 sweep gives 1.67. The multiplier is always below 2 no matter how lopsided the score is.
+/// Missing game counts, or counts where the winner did not win more games, leave k unchanged.
+pub fn margin_multiplier(winner_games: Option<u32>, loser_games: Option<u32>) -> f32 {
+    match (winner_games, loser_games) {
+        (Some(winner_games), Some(loser_games)) if winner_games > loser_games => {
+            let margin = (winner_games - loser_games - 1) as f32;
+            1f32 + margin / (winner_games + loser_games) as f32
+        },
+        _ => 1f32,
+    }
+}
+
+/// The new ratings of two teams with the standard ELO constants, see
+/// `adjust_ratings_with_scale`. An actual score is 1 for a win, 0.5 for a draw and 0 for a loss,
+/// or the series score of the winner.
+///
+/// ```
+/// // Evenly matched teams move k / 2 each way.
+/// let (winner, loser) = elo::adjust_ratings(1500.0, 1500.0, 32.0, 1.0, 0.0)?;
+/// assert_eq!((winner, loser), (1516.0, 1484.0));
+///
+/// // A draw moves the favourite down and the underdog up.
+/// let (favourite, underdog) = elo::adjust_ratings(1600.0, 1400.0, 32.0, 0.5, 0.5)?;
+/// assert!(favourite < 1600.0 && underdog > 1400.0);
+/// # Ok::<(), elo::EloError>(())
+/// ```
+pub fn adjust_ratings(
+    rating1: f32,
+    rating2: f32,
+    k: f32,
+    actual_score1: f32,
+    actual_score2: f32,
+) -> Result<(f32, f32), EloError> {
+    adjust_ratings_with_scale(rating1, rating2, k, actual_score1, actual_score2, EloScale::default())
+}
+
+/// The new ratings of two teams that scored `actual_score1` and `actual_score2`. Fails rather
+/// than returning an infinite or NaN rating, which would otherwise spread to every team the
+/// affected teams play afterwards.
+pub fn adjust_ratings_with_scale(
+    rating1: f32,
+    rating2: f32,
+    k: f32,
+    actual_score1: f32,
+    actual_score2: f32,
+    scale: EloScale,
+) -> Result<(f32, f32), EloError> {
+    let expected_probabilities = get_expected_probabilities_with_scale(rating1, rating2, scale);
+
+    let new_rating1 = rating1 + k * (actual_score1 - expected_probabilities.0);
+    let new_rating2 = rating2 + k * (actual_score2 - expected_probabilities.1);
+
+    if !(new_rating1.is_finite() && new_rating2.is_finite()) {
+        return Err(EloError::NonFiniteRating(format!("adjusting ratings {} and {} with k = {}", rating1, rating2, k)));
+    }
+
+    Ok((new_rating1, new_rating2))
+}
+
+/// A rating algorithm, anything that can predict a match between two ratings and update them once
+/// it has been played. `Elo` is the built-in one, see `apply_match_result_with_system`.
+pub trait RatingSystem {
+    /// The expected scores of teams rated `rating_a` and `rating_b` against each other.
+    fn expected(&self, rating_a: f32, rating_b: f32) -> (f32, f32);
+
+    /// The new ratings of the two teams after they scored `score_a` and `score_b`. `rating_a` is
+    /// the winner, whose score is 1 or the series score of the match, and both score 0.5 in a draw.
+    fn update(&self, rating_a: f32, rating_b: f32, score_a: f32, score_b: f32) -> Result<(f32, f32), EloError>;
+}
+
+/// The ELO rating of `configuration` as a `RatingSystem`, with the k of the bracket the two
+/// ratings fall in, its scale and its limits on how far a rating moves. Home advantage, team k
+/// overrides, provisional k and the other adjustments that need the whole match are only applied
+/// by `apply_match_result`.
+pub struct Elo<'a> {
+    pub configuration: &'a Configuration,
+}
+
+impl RatingSystem for Elo<'_> {
+    fn expected(&self, rating_a: f32, rating_b: f32) -> (f32, f32) {
+        self.configuration.expected_probabilities(rating_a, rating_b)
+    }
+
+    fn update(&self, rating_a: f32, rating_b: f32, score_a: f32, score_b: f32) -> Result<(f32, f32), EloError> {
+        let k = bracket_k_for_match(rating_a, rating_b, self.configuration)?;
+        let (new_rating_a, new_rating_b) = adjust_ratings_with_scale(rating_a, rating_b, k, score_a, score_b, self.configuration.scale())?;
+        Ok((self.configuration.bound_rating(rating_a, new_rating_a), self.configuration.bound_rating(rating_b, new_rating_b)))
+    }
+}
+
+/// The change a single match applies to the ratings of the two teams involved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchUpdate {
+    pub winner: String,
+    pub loser: String,
+    pub draw: bool,
+    pub winner_rating: f32,
+    pub loser_rating: f32,
+    pub winner_k: f32,
+    pub loser_k: f32,
+    pub winner_delta: f32,
+    pub loser_delta: f32,
+    /// The probability of the winner winning before the match, including any home advantage.
+    pub winner_expected: f32,
+}
+
+impl MatchUpdate {
+    pub fn apply_to(&self, standings: &mut Standings) {
+        standings.insert(self.winner.clone(), self.winner_rating + self.winner_delta);
+        standings.insert(self.loser.clone(), self.loser_rating + self.loser_delta);
+    }
+
+    pub fn record_games_played(&self, games_played: &mut GamesPlayed) {
+        *games_played.entry(self.winner.clone()).or_insert(0) += 1;
+        *games_played.entry(self.loser.clone()).or_insert(0) += 1;
+    }
+
+    pub fn record_win(&self, wins: &mut Wins) {
+        if !self.draw {
+            *wins.entry(self.winner.clone()).or_insert(0) += 1;
+        }
+    }
+}
+
+impl fmt::Display for MatchUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verb = if self.draw { "drew with" } else { "beat" };
+        write!(f, "{} ({:.1}) {} {} ({:.1}), ", self.winner, self.winner_rating, verb, self.loser, self.loser_rating)?;
+        if self.winner_k == self.loser_k {
+            write!(f, "k = {}", self.winner_k)?;
+        } else {
+            write!(f, "k = {}/{}", self.winner_k, self.loser_k)?;
+        }
+        write!(f, ": {} {:+.1}, {} {:+.1}", self.winner, self.winner_delta, self.loser, self.loser_delta)
+    }
+}
+
+/// How a match went for one of the teams that played it.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Win => f.pad("win"),
+            Outcome::Loss => f.pad("loss"),
+            Outcome::Draw => f.pad("draw"),
+        }
+    }
+}
+
+/// A single match from the point of view of one of the teams that played it.
+#[derive(Debug, PartialEq)]
+pub struct TeamMatch {
+    pub opponent: String,
+    pub outcome: Outcome,
+    pub rating_before: f32,
+    pub rating_after: f32,
+}
+
+impl MatchUpdate {
+    /// The match as `team` saw it, or `None` if `team` did not play in it.
+    pub fn for_team(&self, team: &str) -> Option<TeamMatch> {
+        let won = if self.draw { Outcome::Draw } else { Outcome::Win };
+        let lost = if self.draw { Outcome::Draw } else { Outcome::Loss };
+        if self.winner == team {
+            Some(TeamMatch { opponent: self.loser.clone(), outcome: won, rating_before: self.winner_rating, rating_after: self.winner_rating + self.winner_delta })
+        } else if self.loser == team {
+            Some(TeamMatch { opponent: self.winner.clone(), outcome: lost, rating_before: self.loser_rating, rating_after: self.loser_rating + self.loser_delta })
+        } else {
+            None
+        }
+    }
+}
+
+/// Formats every match `team` played with the opponent, outcome, rating before and after and the
+/// change since the first of them, followed by the final rating and rank of the team in
+/// `standings`. Fails if `team` is not in `standings`.
+///
+/// Teams are shown by their display name when they have one.
+pub fn format_team_report(team: &str, matches: &[TeamMatch], standings: &Standings, games_played: &GamesPlayed, display_names: &DisplayNames) -> Result<String, EloError> {
+    let final_rating = *standings.get(team).ok_or_else(|| EloError::MissingTeam(team.to_string()))?;
+    let sorted = sorted_standings_with_games(standings, games_played);
+    let rank = sorted.iter().position(|(ranked_team, _)| *ranked_team == team).unwrap_or(0) + 1;
+    let opponent_width = matches.iter().map(|played| display_name(&played.opponent, display_names).len()).max().unwrap_or(0);
+
+    let mut report = format!("{}\n", display_name(team, display_names));
+    let start = matches.first().map_or(final_rating, |played| played.rating_before);
+    for (index, played) in matches.iter().enumerate() {
+        report.push_str(&format!(
+            "{:>4}  vs {:<width$}  {:<4}  {:>7.1} -> {:>7.1}  ({:+.1}, {:+.1} total)\n",
+            index + 1, display_name(&played.opponent, display_names), played.outcome, played.rating_before, played.rating_after,
+            played.rating_after - played.rating_before, played.rating_after - start,
+            width = opponent_width,
+        ));
+    }
+    report.push_str(&format!("Final rating: {:.1}, rank {} of {}\n", final_rating, rank, sorted.len()));
+
+    Ok(report)
+}
+
+/// How well the expected scores of a run of matches predicted what happened, lower is better for
+/// both scores. Draws count as half a win for the winner.
+#[derive(Debug, Default, PartialEq)]
+pub struct Calibration {
+    pub matches: usize,
+    brier_total: f32,
+    log_loss_total: f32,
+}
+
+impl Calibration {
+    /// Smallest probability used for the log loss, so a certain prediction that turns out wrong
+    /// gives a large but finite loss.
+    const MIN_PROBABILITY: f32 = 1e-6;
+
+    pub fn record(&mut self, update: &MatchUpdate) {
+        let outcome = if update.draw { 0.5f32 } else { 1f32 };
+        let expected = update.winner_expected.clamp(Self::MIN_PROBABILITY, 1f32 - Self::MIN_PROBABILITY);
+
+        self.matches += 1;
+        self.brier_total += (update.winner_expected - outcome).powi(2);
+        self.log_loss_total -= outcome * expected.ln() + (1f32 - outcome) * (1f32 - expected).ln();
+    }
+
+    /// The mean squared difference between the expected and actual score of the winner, from 0
+    /// for perfect predictions to 0.25 for always guessing even odds and 1 for always being wrong.
+    pub fn brier_score(&self) -> Option<f32> {
+        (self.matches > 0).then(|| self.brier_total / self.matches as f32)
+    }
+
+    /// The mean negative log likelihood of the results, `ln 2` for always guessing even odds.
+    pub fn log_loss(&self) -> Option<f32> {
+        (self.matches > 0).then(|| self.log_loss_total / self.matches as f32)
+    }
+}
+
+impl fmt::Display for Calibration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Matches predicted: {}", self.matches)?;
+        if let (Some(brier_score), Some(log_loss)) = (self.brier_score(), self.log_loss()) {
+            writeln!(f, "Brier score: {:.4}", brier_score)?;
+            writeln!(f, "Log loss: {:.4}", log_loss)?;
+        }
+        Ok(())
+    }
+}
+
+/// Golden-section search in `tune_k` stops once the range holding the best k is this narrow.
+const TUNING_TOLERANCE: f32 = 0.01;
+
+/// The single k found by `tune_k` and the calibration of the matches rated with it.
+#[derive(Debug, PartialEq)]
+pub struct Tuning {
+    pub k: f32,
+    pub calibration: Calibration,
+}
+
+impl fmt::Display for Tuning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Best k: {:.2}", self.k)?;
+        write!(f, "{}", self.calibration)
+    }
+}
+
+/// The calibration of `results` rated with a single k bracket of `k` in place of the configured
+/// brackets. Team k overrides and provisional k still apply.
+pub fn calibration_for_k(results: &[MatchResult], standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration, k: f32) -> Result<Calibration, EloError> {
+    let configuration = Configuration {
+        k_brackets: vec![KBracket { start: 0, k }],
+        bracket_interpolation: false,
+        ..configuration.clone()
+    };
+    let mut calibration = Calibration::default();
+    apply_match_results_inspect_with_games(results, standings, games_played, &configuration, |update, _| {
+        calibration.record(update);
+        Ok::<(), EloError>(())
+    })?;
+    Ok(calibration)
+}
+
+/// Searches `[min_k, max_k]` for the single k whose predictions of `results` have the lowest log
+/// loss, as scored by `calibration_for_k`.
+///
+/// It is a golden-section search, so it needs a log loss that falls and then rises across the
+/// range, which it usually does: too small a k leaves the ratings behind the results and too large
+/// a k chases every upset.
+///
+/// # Errors
+///
+/// `EloError::InvalidConfig` unless `0 < min_k < max_k` with both finite, or when none of
+/// `results` is rated so there is nothing to predict. Otherwise the errors of applying the matches.
+pub fn tune_k(results: &[MatchResult], standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration, min_k: f32, max_k: f32) -> Result<Tuning, EloError> {
+    if !(0f32 < min_k && min_k < max_k && max_k.is_finite()) {
+        return Err(EloError::InvalidConfig(format!("k must be tuned over a positive range, got {} to {}", min_k, max_k)));
+    }
+
+    let log_loss = |k| {
+        calibration_for_k(results, standings, games_played, configuration, k)?
+            .log_loss()
+            .ok_or_else(|| EloError::InvalidConfig("there are no rated matches to tune k on".to_string()))
+    };
+    let inverse_phi = (5f32.sqrt() - 1f32) / 2f32;
+    let (mut low, mut high) = (min_k, max_k);
+    let mut lower = high - inverse_phi * (high - low);
+    let mut upper = low + inverse_phi * (high - low);
+    let (mut lower_loss, mut upper_loss) = (log_loss(lower)?, log_loss(upper)?);
+
+    // Each step keeps the part of the range around the lower of the two losses and reuses that
+    // point, so only one new k is rated per step.
+    while high - low > TUNING_TOLERANCE {
+        if lower_loss < upper_loss {
+            high = upper;
+            upper = lower;
+            upper_loss = lower_loss;
+            lower = high - inverse_phi * (high - low);
+            lower_loss = log_loss(lower)?;
+        } else {
+            low = lower;
+            lower = upper;
+            lower_loss = upper_loss;
+            upper = low + inverse_phi * (high - low);
+            upper_loss = log_loss(upper)?;
+        }
+    }
+
+    let k = (low + high) / 2f32;
+    Ok(Tuning { k, calibration: calibration_for_k(results, standings, games_played, configuration, k)? })
+}
+
+/// The wins a team was expected to take from its matches in a run against the wins it took.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct TeamPerformance {
+    pub matches: u32,
+    /// The sum of the team's expected scores going into each of its matches.
+    pub expected_wins: f32,
+    /// Draws count as half a win.
+    pub actual_wins: f32,
+}
+
+impl TeamPerformance {
+    /// Positive for teams that won more than their ratings predicted, which are likely underrated.
+    pub fn surplus(&self) -> f32 {
+        self.actual_wins - self.expected_wins
+    }
+}
+
+/// Expected against actual wins for every team that played in a run. Displays one line per team,
+/// from the team that most outperformed its ratings to the one that most underperformed them.
+#[derive(Debug, Default, PartialEq)]
+pub struct Performance {
+    pub teams: HashMap<String, TeamPerformance>,
+}
+
+impl Performance {
+    pub fn record(&mut self, update: &MatchUpdate) {
+        let winner_wins = if update.draw { 0.5f32 } else { 1f32 };
+        for (team, expected, actual) in [
+            (&update.winner, update.winner_expected, winner_wins),
+            (&update.loser, 1f32 - update.winner_expected, 1f32 - winner_wins),
+        ] {
+            let performance = self.teams.entry(team.clone()).or_default();
+            performance.matches += 1;
+            performance.expected_wins += expected;
+            performance.actual_wins += actual;
+        }
+    }
+}
+
+impl fmt::Display for Performance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut teams: Vec<(&String, &TeamPerformance)> = self.teams.iter().collect();
+        teams.sort_by(|(team_a, a), (team_b, b)| b.surplus().partial_cmp(&a.surplus()).unwrap_or(std::cmp::Ordering::Equal).then_with(|| team_a.cmp(team_b)));
+        for (team, performance) in teams {
+            writeln!(
+                f, "{}: {} matches, expected {:.2} wins, won {:.1} ({:+.2})",
+                team, performance.matches, performance.expected_wins, performance.actual_wins, performance.surplus()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The rating `k_selection` picks for a match and the bracket it falls in. Ratings below every
+/// bracket use the lowest one.
+fn bracket_for_match(winner_rating: f32, loser_rating: f32, configuration: &Configuration) -> (f32, Option<KBracket>) {
+    let bracket_rating = configuration.k_selection.bracket_rating(winner_rating, loser_rating);
+    let bracket = configuration.k_brackets.iter()
+        .filter(|bracket| bracket_rating >= bracket.start as f32)
+        .max_by_key(|bracket| bracket.start)
+        .or_else(|| configuration.lowest_bracket())
+        .copied();
+    (bracket_rating, bracket)
+}
+
+/// The k of `bracket` for a match at `bracket_rating`, or the interpolated k with
+/// `bracket_interpolation`.
+fn bracket_k(bracket_rating: f32, bracket: Option<KBracket>, configuration: &Configuration) -> Result<f32, EloError> {
+    let k = if configuration.bracket_interpolation {
+        interpolated_scaling_for_rating(bracket_rating, &configuration.k_brackets)
+    } else {
+        bracket.map(|bracket| bracket.k)
+    };
+    k.ok_or(EloError::NoKBracket(bracket_rating))
+}
+
+/// The bracket k for a match, see `bracket_for_match`.
+fn bracket_k_for_match(winner_rating: f32, loser_rating: f32, configuration: &Configuration) -> Result<f32, EloError> {
+    let (bracket_rating, bracket) = bracket_for_match(winner_rating, loser_rating, configuration);
+    bracket_k(bracket_rating, bracket, configuration)
+}
+
+/// The new ratings of the winner and loser of a `series` won outright, without building
+/// `Standings` or a `MatchResult`.
+///
+/// Both teams use the bracket k, scaled by the upset factor, and the rating floor and expected
+/// score constants of `configuration` apply. Team overrides, provisional k and home advantage need team names and
+/// games played, use `get_match_update` for those.
+///
+/// ```
+/// let config: elo::Configuration = serde_json::from_str(r#"{
+///     "bo1_score": 1, "bo3_score": 1.25, "bo5_score": 1.5,
+///     "k_brackets": [{ "start": 0, "k": 32 }]
+/// }"#)?;
+/// let (winner, loser) = elo::update_ratings(1500.0, 1500.0, elo::SeriesKind::Bo3, &config)?;
+/// assert_eq!((winner, loser), (1524.0, 1484.0));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn update_ratings(winner_rating: f32, loser_rating: f32, series: SeriesKind, configuration: &Configuration) -> Result<(f32, f32), EloError> {
+    let expected = configuration.expected_probabilities(winner_rating, loser_rating).0;
+    let k = bracket_k_for_match(winner_rating, loser_rating, configuration)? * configuration.upset_multiplier(expected, 1f32);
+    let winner_score = get_series_win_weight_from_config(configuration)(series);
+
+    let (new_winner_rating, new_loser_rating) = adjust_ratings_with_scale(winner_rating, loser_rating, k, winner_score, 0f32, configuration.scale())?;
+    let mut new_winner_rating = winner_rating + configuration.limit_delta(winner_rating, new_winner_rating - winner_rating);
+    let mut new_loser_rating = loser_rating + configuration.limit_delta(loser_rating, new_loser_rating - loser_rating);
+    if let Some(rating_floor) = configuration.rating_floor {
+        new_winner_rating = new_winner_rating.max(rating_floor);
+        new_loser_rating = new_loser_rating.max(rating_floor);
+    }
+
+    Ok((new_winner_rating, new_loser_rating))
+}
+
+/// Computes the update `result` applies to `standings`. `games_played` is the number of games
+/// each team has played so far and is only used to decide whether a team is still provisional.
+/// The two teams are treated as meeting for the first time, see `get_match_update_with_meetings`.
+pub fn get_match_update(result: &MatchResult, standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration) -> Result<MatchUpdate, EloError> {
+    get_match_update_with_meetings(result, standings, games_played, &Meetings::default(), configuration)
+}
+
+/// Same as `get_match_update` for teams that may have met before in the run, as recorded in
+/// `meetings`, which scales k by `Configuration::repeat_multiplier`.
+pub fn get_match_update_with_meetings(result: &MatchResult, standings: &Standings, games_played: &GamesPlayed, meetings: &Meetings, configuration: &Configuration) -> Result<MatchUpdate, EloError> {
+    explain_match_with_meetings(result, standings, games_played, meetings, configuration).map(|explanation| explanation.update)
+}
+
+/// How many times each pair of teams has met so far in a run, in either order.
+#[derive(Default)]
+pub struct Meetings {
+    counts: HashMap<String, HashMap<String, u32>>,
+}
+
+impl Meetings {
+    fn ordered<'a>(team_a: &'a str, team_b: &'a str) -> (&'a str, &'a str) {
+        if team_a <= team_b { (team_a, team_b) } else { (team_b, team_a) }
+    }
+
+    pub fn between(&self, team_a: &str, team_b: &str) -> u32 {
+        let (first, second) = Self::ordered(team_a, team_b);
+        self.counts.get(first).and_then(|opponents| opponents.get(second)).copied().unwrap_or(0)
+    }
+
+    /// Counts the meeting of the two teams of `update`.
+    pub fn record(&mut self, update: &MatchUpdate) {
+        let (first, second) = Self::ordered(&update.winner, &update.loser);
+        *self.counts.entry(first.to_string()).or_default().entry(second.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// Where the k a team was adjusted with came from, before any multipliers.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum KSource {
+    Bracket,
+    Override,
+    Provisional,
+}
+
+impl fmt::Display for KSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KSource::Bracket => write!(f, "bracket"),
+            KSource::Override => write!(f, "team override"),
+            KSource::Provisional => write!(f, "provisional"),
+        }
+    }
+}
+
+/// Every intermediate value `get_match_update` goes through for one match, displayed as a
+/// labelled step-by-step breakdown.
+pub struct MatchExplanation {
+    pub series: SeriesKind,
+    pub winner_advantage: f32,
+    pub loser_advantage: f32,
+    /// Expected scores of the winner and loser, including any home advantage.
+    pub expected: (f32, f32),
+    pub k_selection: KSelection,
+    pub bracket_rating: f32,
+    /// The bracket `bracket_rating` falls in, or the lowest one when it is below them all.
+    pub bracket: Option<KBracket>,
+    pub winner_k_source: KSource,
+    pub loser_k_source: KSource,
+    pub winner_base_k: f32,
+    pub loser_base_k: f32,
+    pub margin_multiplier: f32,
+    pub importance: f32,
+    pub upset_multiplier: f32,
+    pub repeat_multiplier: f32,
+    pub winner_score: f32,
+    pub loser_score: f32,
+    pub update: MatchUpdate,
+}
+
+impl fmt::Display for MatchExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let update = &self.update;
+        let verb = if update.draw { "drew with" } else { "beat" };
+        writeln!(f, "Match: {} {} {} ({:?})", update.winner, verb, update.loser, self.series)?;
+        writeln!(f, "Ratings: {} {:.1}, {} {:.1}", update.winner, update.winner_rating, update.loser, update.loser_rating)?;
+        if self.winner_advantage != 0f32 || self.loser_advantage != 0f32 {
+            writeln!(f, "Home advantage: {} {:+.1}, {} {:+.1}", update.winner, self.winner_advantage, update.loser, self.loser_advantage)?;
+        }
+        writeln!(f, "Expected probabilities: {} {:.4}, {} {:.4}", update.winner, self.expected.0, update.loser, self.expected.1)?;
+        let basis = match self.k_selection {
+            KSelection::Average => "average rating",
+            KSelection::Winner => "winner rating",
+            KSelection::Loser => "loser rating",
+            KSelection::Difference => "rating difference",
+        };
+        match self.bracket {
+            Some(bracket) if self.bracket_rating >= bracket.start as f32 => writeln!(
+                f, "k bracket: {} {:.1} falls in the bracket starting at {}, k {}",
+                basis, self.bracket_rating, bracket.start, bracket.k
+            )?,
+            Some(bracket) => writeln!(
+                f, "k bracket: {} {:.1} is below every bracket, using the lowest starting at {}, k {}",
+                basis, self.bracket_rating, bracket.start, bracket.k
+            )?,
+            None => writeln!(f, "k bracket: {} {:.1} has no bracket", basis, self.bracket_rating)?,
+        }
+        writeln!(
+            f, "Base k: {} {} ({}), {} {} ({})",
+            update.winner, self.winner_base_k, self.winner_k_source, update.loser, self.loser_base_k, self.loser_k_source
+        )?;
+        writeln!(
+            f, "Multipliers: margin {:.3}, importance {:.3}, upset {:.3}, repeat matchup {:.3}",
+            self.margin_multiplier, self.importance, self.upset_multiplier, self.repeat_multiplier
+        )?;
+        writeln!(f, "k: {} {}, {} {}", update.winner, update.winner_k, update.loser, update.loser_k)?;
+        writeln!(f, "Series weight: {} {}, {} {}", update.winner, self.winner_score, update.loser, self.loser_score)?;
+        writeln!(
+            f, "Deltas: {} {:+.1} -> {:.1}, {} {:+.1} -> {:.1}",
+            update.winner, update.winner_delta, update.winner_rating + update.winner_delta,
+            update.loser, update.loser_delta, update.loser_rating + update.loser_delta
+        )
+    }
+}
+
+/// Computes the update `result` applies to `standings` like `get_match_update`, keeping every
+/// intermediate value along the way.
+pub fn explain_match(result: &MatchResult, standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration) -> Result<MatchExplanation, EloError> {
+    explain_match_with_meetings(result, standings, games_played, &Meetings::default(), configuration)
+}
+
+fn explain_match_with_meetings(result: &MatchResult, standings: &Standings, games_played: &GamesPlayed, meetings: &Meetings, configuration: &Configuration) -> Result<MatchExplanation, EloError> {
+    let winner = configuration.canonical_team(&result.winner);
+    let loser = configuration.canonical_team(&result.loser);
+    if winner == loser {
+        return Err(EloError::SelfMatch(winner.to_string()));
+    }
+
+    let team_state = |team| TeamState {
+        team,
+        rating: standings.get(team).copied().unwrap_or_else(|| configuration.default_rating()),
+        games: games_played.get(team).copied().unwrap_or(0),
+    };
+    let rated = rate_match(result, team_state(winner), team_state(loser), meetings.between(winner, loser), configuration)?;
+    let update = rated.update(winner, loser, result.draw);
+
+    Ok(MatchExplanation {
+        series: result.series,
+        winner_advantage: rated.winner_advantage,
+        loser_advantage: rated.loser_advantage,
+        expected: rated.expected,
+        k_selection: configuration.k_selection,
+        bracket_rating: rated.bracket_rating,
+        bracket: rated.bracket,
+        winner_k_source: rated.winner_k_source,
+        loser_k_source: rated.loser_k_source,
+        winner_base_k: rated.winner_base_k,
+        loser_base_k: rated.loser_base_k,
+        margin_multiplier: rated.margin_multiplier,
+        importance: rated.importance,
+        upset_multiplier: rated.upset_multiplier,
+        repeat_multiplier: rated.repeat_multiplier,
+        winner_score: rated.winner_score,
+        loser_score: rated.loser_score,
+        update,
+    })
+}
+
+/// The actual scores of the winner and loser of `result`, its margin or series score and 0, or 0.5
+/// each for a draw.
+fn match_scores(result: &MatchResult, configuration: &Configuration) -> (f32, f32) {
+    if result.draw {
+        return (0.5f32, 0.5f32);
+    }
+    let score = configuration.margin_score(result.series, result.winner_games, result.loser_games)
+        .unwrap_or_else(|| get_series_win_weight_from_config(configuration)(result.series));
+    (score, 0f32)
+}
+
+/// A team going into a match, its canonical name along with its rating and games played so far.
+#[derive(Copy, Clone)]
+struct TeamState<'a> {
+    team: &'a str,
+    rating: f32,
+    games: u32,
+}
+
+/// Everything `rate_match` works out for a match, without the team names so no strings have to
+/// be allocated for it.
+struct RatedMatch {
+    winner_rating: f32,
+    loser_rating: f32,
+    winner_advantage: f32,
+    loser_advantage: f32,
+    expected: (f32, f32),
+    bracket_rating: f32,
+    bracket: Option<KBracket>,
+    winner_k_source: KSource,
+    loser_k_source: KSource,
+    winner_base_k: f32,
+    loser_base_k: f32,
+    margin_multiplier: f32,
+    importance: f32,
+    upset_multiplier: f32,
+    repeat_multiplier: f32,
+    winner_score: f32,
+    loser_score: f32,
+    winner_k: f32,
+    loser_k: f32,
+    winner_delta: f32,
+    loser_delta: f32,
+}
+
+impl RatedMatch {
+    /// The update of the rated match between `winner` and `loser`.
+    fn update(&self, winner: &str, loser: &str, draw: bool) -> MatchUpdate {
+        MatchUpdate {
+            winner: winner.to_string(),
+            loser: loser.to_string(),
+            draw,
+            winner_rating: self.winner_rating,
+            loser_rating: self.loser_rating,
+            winner_k: self.winner_k,
+            loser_k: self.loser_k,
+            winner_delta: self.winner_delta,
+            loser_delta: self.loser_delta,
+            winner_expected: self.expected.0,
+        }
+    }
+}
+
+/// Rates a match between two distinct canonical teams. Shared by the name keyed standings of
+/// `explain_match` and the interned ids of `apply_match_results`.
+fn rate_match(result: &MatchResult, winner: TeamState, loser: TeamState, previous_meetings: u32, configuration: &Configuration) -> Result<RatedMatch, EloError> {
+    let winner_rating = winner.rating;
+    let loser_rating = loser.rating;
+
+    // A margin score already accounts for the game score, so the margin multiplier is skipped.
+    let margin_score = configuration.margin_score(result.series, result.winner_games, result.loser_games);
+    let (winner_score, loser_score) = match_scores(result, configuration);
+
+    let (bracket_rating, bracket) = bracket_for_match(winner_rating, loser_rating, configuration);
+    let team_k = |state: TeamState| match (configuration.team_k_overrides.get(state.team), configuration.provisional_k) {
+        (Some(k), _) => Ok((*k, KSource::Override)),
+        (None, Some(provisional_k)) if state.games < configuration.provisional_games => Ok((provisional_k, KSource::Provisional)),
+        _ => bracket_k(bracket_rating, bracket, configuration).map(|k| (k, KSource::Bracket)),
+    };
+    // The home team plays as if it were rated `home_advantage` higher, but the bonus is removed
+    // again so it never ends up in the stored rating.
+    let home_advantage = configuration.home_advantage.unwrap_or(0f32);
+    let (winner_advantage, loser_advantage) = match result.home.as_deref().map(|home| configuration.canonical_team(home)) {
+        Some(home) if home == winner.team => (home_advantage, 0f32),
+        Some(home) if home == loser.team => (0f32, home_advantage),
+        _ => (0f32, 0f32),
+    };
+    let scale = configuration.scale();
+    let effective_winner_rating = winner_rating + winner_advantage;
+    let effective_loser_rating = loser_rating + loser_advantage;
+
+    let expected_probabilities = configuration.expected_probabilities(effective_winner_rating, effective_loser_rating);
+    let expected = expected_probabilities.0;
+    let outcome = if result.draw { 0.5f32 } else { 1f32 };
+    let margin_multiplier = match margin_score {
+        Some(_) => 1f32,
+        None => margin_multiplier(result.winner_games, result.loser_games),
+    };
+    let importance = result.importance.unwrap_or(1f32);
+    let upset_multiplier = configuration.upset_multiplier(expected, outcome);
+    let repeat_multiplier = configuration.repeat_multiplier(previous_meetings);
+    let multiplier = margin_multiplier * importance * upset_multiplier * repeat_multiplier;
+    let (winner_base_k, winner_k_source) = team_k(winner)?;
+    let (loser_base_k, loser_k_source) = team_k(loser)?;
+    let winner_k = winner_base_k * multiplier;
+    let loser_k = loser_base_k * multiplier;
+
+    let non_finite = |_| EloError::NonFiniteRating(format!("the match between '{}' and '{}'", winner.team, loser.team));
+    let new_ratings = (
+        adjust_ratings_with_scale(effective_winner_rating, effective_loser_rating, winner_k, winner_score, loser_score, scale).map_err(non_finite)?.0 - winner_advantage,
+        adjust_ratings_with_scale(effective_winner_rating, effective_loser_rating, loser_k, winner_score, loser_score, scale).map_err(non_finite)?.1 - loser_advantage,
+    );
+    let new_ratings = (
+        configuration.bound_rating(winner_rating, new_ratings.0),
+        configuration.bound_rating(loser_rating, new_ratings.1),
+    );
+
+    Ok(RatedMatch {
+        winner_rating,
+        loser_rating,
+        winner_advantage,
+        loser_advantage,
+        expected: expected_probabilities,
+        bracket_rating,
+        bracket,
+        winner_k_source,
+        loser_k_source,
+        winner_base_k,
+        loser_base_k,
+        margin_multiplier,
+        importance,
+        upset_multiplier,
+        repeat_multiplier,
+        winner_score,
+        loser_score,
+        winner_k,
+        loser_k,
+        winner_delta: new_ratings.0 - winner_rating,
+        loser_delta: new_ratings.1 - loser_rating,
+    })
+}
+
+/// Sorts matches chronologically by `date`, keeping the original order of matches played on the
+/// same date. Matches without a date are moved after every dated match, in their original order.
+///
+/// Dates are compared as strings, which orders ISO-8601 dates correctly as long as every date is
+/// written the same way.
+pub fn sort_by_date(results: &mut [MatchResult]) {
+    results.sort_by(|a, b| match (a.date.as_ref(), b.date.as_ref()) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
+/// Applies a free-for-all as if every pair of teams had played a Bo1 won by the better placed
+/// team.
+///
+/// Every pairwise update is computed from the ratings before the free-for-all and the deltas of
+/// each team are summed. Each pair is weighted by `1 / (teams - 1)`, as if it had an `importance`
+/// of that, so a team finishing first or last in a free-for-all moves about as much as it would
+/// for winning or losing a single match against the same field. `max_delta_per_match`, ceiling
+/// compression and the rating floor are applied once to each team's summed change, so the size
+/// of the field does not change how much they hold a team back. Like `apply_match_result` every
+/// team is treated as having no games played and team names are resolved through the aliases.
+///
+/// Unrated free-for-alls leave the standings as they are. Ignored teams are left out of the
+/// placements, as if they had not played, and the other teams keep their order.
+///
+/// Free-for-alls are only rated through the library, `update` reads two team matches.
+pub fn apply_free_for_all(result: &FreeForAll, standings: &Standings, configuration: &Configuration) -> Result<Standings, EloError> {
+    let placements: Vec<&str> = result.placements.iter().map(|team| configuration.canonical_team(team)).collect();
+    let mut seen = HashSet::new();
+    if let Some(duplicate) = placements.iter().find(|team| !seen.insert(**team)) {
+        return Err(EloError::SelfMatch(duplicate.to_string()));
+    }
+
+    let mut new_standings = standings.clone();
+    let placements: Vec<&str> = placements.into_iter().filter(|team| !configuration.ignored_teams.contains(*team)).collect();
+    if !result.rated || placements.len() < 2 {
+        return Ok(new_standings);
+    }
+
+    // The pairs are rated without the limits, which are then applied to the sums.
+    let unbounded = Configuration {
+        max_delta_per_match: None,
+        ceiling_compression: None,
+        rating_floor: None,
+        ..configuration.clone()
+    };
+    let weight = 1f32 / (placements.len() - 1) as f32;
+    let mut deltas: HashMap<&str, f32> = HashMap::new();
+    for (place, winner) in placements.iter().enumerate() {
+        for loser in placements[place + 1..].iter() {
+            let pair = MatchResult { winner: winner.to_string(), loser: loser.to_string(), importance: Some(weight), ..Default::default() };
+            let update = get_match_update(&pair, standings, &GamesPlayed::new(), &unbounded)?;
+            *deltas.entry(winner).or_insert(0f32) += update.winner_delta;
+            *deltas.entry(loser).or_insert(0f32) += update.loser_delta;
+        }
+    }
+
+    for (team, delta) in deltas {
+        let rating = standings.get(team).copied().unwrap_or(configuration.default_rating());
+        new_standings.insert(team.to_string(), configuration.bound_rating(rating, rating + delta));
+    }
+
+    Ok(new_standings)
+}
+
+/// Days since 1970-01-01 of an ISO-8601 date, only the leading `YYYY-MM-DD` is read so dates
+/// with times are accepted too.
+fn days_since_epoch(date: &str) -> Option<i64> {
+    let mut parts = date.get(..10)?.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Howard Hinnant's days_from_civil, with years starting in March so leap days come last.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+/// Multiplies the `importance` of every match by `0.5 ^ (age / half_life)` so older matches move
+/// ratings less.
+///
+/// When every match has a date the age is the number of days before the most recent match,
+/// otherwise it is the number of matches played after it, so the last match always keeps its
+/// full k and one `half_life` back k is halved. The weight multiplies whatever k the match would
+/// otherwise use, bracket, override or provisional, along with its other multipliers.
+///
+/// Unrated matches are left as they are and do not count towards the age of the others, since
+/// they never change a rating. Matches against ignored teams should be dropped before weighting,
+/// as `update` does, for the same reason.
+pub fn weight_by_recency(results: &mut [MatchResult], half_life: f32) {
+    let mut rated: Vec<&mut MatchResult> = results.iter_mut().filter(|result| result.rated).collect();
+    let days: Option<Vec<i64>> = rated
+        .iter()
+        .map(|result| result.date.as_deref().and_then(days_since_epoch))
+        .collect();
+    let ages: Vec<f32> = match days {
+        Some(days) => {
+            let latest = days.iter().copied().max().unwrap_or(0);
+            days.iter().map(|day| (latest - day) as f32).collect()
+        },
+        None => (0..rated.len()).rev().map(|age| age as f32).collect(),
+    };
+
+    for (result, age) in rated.iter_mut().zip(ages) {
+        let weight = 0.5f32.powf(age / half_life);
+        result.importance = Some(result.importance.unwrap_or(1f32) * weight);
+    }
+}
+
+/// Swaps the winner and loser of the matches at `indices`, for asking what the standings would
+/// look like had those results gone the other way. Game counts are swapped along with the teams,
+/// so a flipped match keeps its margin.
+///
+/// Fails without changing anything if an index is out of range or names a draw, which has no
+/// other way to go.
+pub fn flip_results(results: &mut [MatchResult], indices: &[usize]) -> Result<(), String> {
+    for index in indices {
+        match results.get(*index) {
+            None => return Err(format!("cannot flip match {}, there are only {} matches", index, results.len())),
+            Some(result) if result.draw => return Err(format!("cannot flip match {}, it is a draw", index)),
+            Some(_) => {},
+        }
+    }
+
+    let flipped: HashSet<usize> = indices.iter().copied().collect();
+    for index in flipped {
+        let result = &mut results[index];
+        std::mem::swap(&mut result.winner, &mut result.loser);
+        std::mem::swap(&mut result.winner_games, &mut result.loser_games);
+    }
+    Ok(())
+}
+
+/// Keeps only the matches played between `since` and `until`, both inclusive and either open
+/// ended when `None`. Dates are compared by day, so any time of day in them is ignored.
+///
+/// Matches without a readable date are dropped when either bound is given, unless `keep_undated`
+/// is set. An unreadable bound is an error.
+pub fn filter_by_date(results: &mut Vec<MatchResult>, since: Option<&str>, until: Option<&str>, keep_undated: bool) -> Result<(), String> {
+    let parse_bound = |bound: Option<&str>| match bound {
+        Some(date) => days_since_epoch(date).map(Some).ok_or_else(|| format!("invalid date '{}', expected YYYY-MM-DD", date)),
+        None => Ok(None),
+    };
+    let since = parse_bound(since)?;
+    let until = parse_bound(until)?;
+    if since.is_none() && until.is_none() {
+        return Ok(());
+    }
+
+    results.retain(|result| match result.date.as_deref().and_then(days_since_epoch) {
+        Some(day) => since.is_none_or(|since| day >= since) && until.is_none_or(|until| day <= until),
+        None => keep_undated,
+    });
+    Ok(())
+}
+
+/// A single game of a series, as given by feeds that report every game separately.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct GameResult {
+    /// Games with the same `series_id` belong to the same series.
+    pub series_id: String,
+    pub winner: String,
+    pub loser: String,
+    /// The kind of series the game was part of, worked out from the game score when absent.
+    #[serde(default)]
+    pub series: Option<SeriesKind>,
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+/// Folds game-level results into one `MatchResult` per series, in the order each series first
+/// appears. The team that won more games wins the series, with the game score as its margin,
+/// and a series with the games split evenly is a draw.
+///
+/// The series kind is taken from the games when any of them gives one. Otherwise it is the
+/// shortest best-of the winner could have won in, a Bo3 for 2-0 or 2-1 and a Bo5 for 3-0, and a
+/// draw is a `Custom` series of the games played. The date of a series is the date of its first
+/// game. Fails if the games of a series are not all between the same two teams.
+pub fn group_games_into_series(games: &[GameResult]) -> Result<Vec<MatchResult>, String> {
+    let mut series_order: Vec<&str> = Vec::new();
+    let mut series_games: HashMap<&str, Vec<&GameResult>> = HashMap::new();
+    for game in games {
+        series_games
+            .entry(game.series_id.as_str())
+            .or_insert_with(|| {
+                series_order.push(game.series_id.as_str());
+                Vec::new()
+            })
+            .push(game);
+    }
+
+    series_order
+        .into_iter()
+        .map(|series_id| {
+            let games = &series_games[series_id];
+            let first = games[0];
+            let (team_a, team_b) = (first.winner.as_str(), first.loser.as_str());
+            let mut wins = (0u32, 0u32);
+            for game in games.iter() {
+                match (game.winner.as_str(), game.loser.as_str()) {
+                    (winner, loser) if (winner, loser) == (team_a, team_b) => wins.0 += 1,
+                    (winner, loser) if (winner, loser) == (team_b, team_a) => wins.1 += 1,
+                    (winner, loser) => return Err(format!(
+                        "series '{}' has a game between {} and {}, but its first game was between {} and {}",
+                        series_id, winner, loser, team_a, team_b
+                    )),
+                }
+            }
+
+            let (winner, loser, winner_games, loser_games) = if wins.0 >= wins.1 {
+                (team_a, team_b, wins.0, wins.1)
+            } else {
+                (team_b, team_a, wins.1, wins.0)
+            };
+            let draw = winner_games == loser_games;
+            let series = games.iter().find_map(|game| game.series).unwrap_or_else(|| {
+                let best_of = if draw { winner_games + loser_games } else { 2 * winner_games - 1 };
+                match best_of {
+                    1 => SeriesKind::Bo1,
+                    3 => SeriesKind::Bo3,
+                    5 => SeriesKind::Bo5,
+                    7 => SeriesKind::Bo7,
+                    games => SeriesKind::Custom(games),
+                }
+            });
+
+            Ok(MatchResult {
+                winner: winner.to_string(),
+                loser: loser.to_string(),
+                series,
+                draw,
+                winner_games: Some(winner_games),
+                loser_games: Some(loser_games),
+                date: first.date.clone(),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Parses game-level results from JSON, TOML or YAML, or CSV with a `series_id,winner,loser`
+/// header and optional `series` and `date` columns.
+pub fn parse_game_results_from_path(path: &Path) -> Result<Vec<GameResult>, EloError> {
+    if has_extension(path, "csv") {
+        let mut csv_reader = csv::Reader::from_reader(open_reader(path)?);
+        return csv_reader.deserialize().collect::<Result<_, _>>().map_err(|error| parse_error(path, error));
+    }
+
+    parse_type_from_path(path)
+}
+
+/// Applies a single match. Every team is treated as having no games played, use
+/// `apply_match_results` when provisional k values matter.
+pub fn apply_match_result(result: &MatchResult, standings: &Standings, configuration: &Configuration) -> Result<Standings, EloError> {
+    if !configuration.counts_match(result) {
+        return Ok(standings.clone());
+    }
+
+    let update = get_match_update(result, standings, &GamesPlayed::new(), configuration)?;
+
+    let mut new_standings = standings.clone();
+    update.apply_to(&mut new_standings);
+
+    Ok(new_standings)
+}
+
+/// Applies a single match like `apply_match_result` but rates it with `system` instead of the
+/// built-in ELO. `configuration` still decides which matches count, resolves aliases, gives new
+/// teams their starting rating and scores each series.
+pub fn apply_match_result_with_system(result: &MatchResult, standings: &Standings, configuration: &Configuration, system: &dyn RatingSystem) -> Result<Standings, EloError> {
+    if !configuration.counts_match(result) {
+        return Ok(standings.clone());
+    }
+
+    let winner = configuration.canonical_team(&result.winner);
+    let loser = configuration.canonical_team(&result.loser);
+    if winner == loser {
+        return Err(EloError::SelfMatch(winner.to_string()));
+    }
+
+    let rating = |team| standings.get(team).copied().unwrap_or_else(|| configuration.default_rating());
+    let (winner_score, loser_score) = match_scores(result, configuration);
+    let (winner_rating, loser_rating) = system.update(rating(winner), rating(loser), winner_score, loser_score)?;
+    if !(winner_rating.is_finite() && loser_rating.is_finite()) {
+        return Err(EloError::NonFiniteRating(format!("the match between '{}' and '{}'", winner, loser)));
+    }
+
+    let mut new_standings = standings.clone();
+    new_standings.insert(winner.to_string(), winner_rating);
+    new_standings.insert(loser.to_string(), loser_rating);
+
+    Ok(new_standings)
+}
+
+/// Reverses the change `result` made to `standings`, the inverse of `apply_match_result`.
+///
+/// There is no closed form for the ratings before a match, so this searches for the ratings that
+/// `apply_match_result` would have turned into the current ones, starting from the current
+/// ratings and refining until the change between passes is negligible.
+///
+/// The inverse is only exact when `result` was the last match either team played. If other
+/// matches involving them have been applied since, the ratings are recomputed from the current
+/// ratings rather than the ones at the time, so the result is only approximate. Ratings that were
+/// clamped by `rating_floor` cannot be recovered, and teams are never removed from the standings
+/// even if the match added them.
+pub fn undo_match_result(result: &MatchResult, standings: &Standings, configuration: &Configuration) -> Result<Standings, EloError> {
+    const MAX_ITERATIONS: usize = 100;
+    const TOLERANCE: f32 = 1e-4;
+
+    if !configuration.counts_match(result) {
+        return Ok(standings.clone());
+    }
+
+    let rating = |team: &str| standings.get(team).copied().ok_or_else(|| EloError::MissingTeam(team.to_string()));
+    let winner_after = rating(configuration.canonical_team(&result.winner))?;
+    let loser_after = rating(configuration.canonical_team(&result.loser))?;
+
+    let mut guess = standings.clone();
+    for _ in 0..MAX_ITERATIONS {
+        let update = get_match_update(result, &guess, &GamesPlayed::new(), configuration)?;
+        let winner_before = winner_after - update.winner_delta;
+        let loser_before = loser_after - update.loser_delta;
+
+        let change = (winner_before - update.winner_rating).abs().max((loser_before - update.loser_rating).abs());
+        guess.insert(update.winner.clone(), winner_before);
+        guess.insert(update.loser.clone(), loser_before);
+
+        if change < TOLERANCE {
+            break;
+        }
+    }
+
+    Ok(guess)
+}
+
+/// Applies every match in order, counting the games each team plays along the way. Stops at the
+/// first match that cannot be applied.
+///
+/// Team names are interned to ids once up front and the matches are applied to ratings indexed
+/// by id, so names are neither hashed nor cloned per match. The result is the same as
+/// `apply_match_results_inspect`, which keeps the names for every update it reports.
+pub fn apply_match_results(results: &[MatchResult], standings: &Standings, configuration: &Configuration) -> Result<Standings, EloError> {
+    apply_interned(results, standings, &GamesPlayed::new(), configuration, None::<fn(&MatchUpdate) -> Result<(), EloError>>)
+}
+
+/// Same as `apply_match_results_inspect_with_games`, but `inspect` is only given each update and
+/// not the standings after it, so the matches can be applied on the interned ids of
+/// `apply_match_results`. Faster whenever the standings in between matches are not needed.
+pub fn apply_match_results_inspect_updates<F, E>(results: &[MatchResult], standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration, inspect: F) -> Result<Standings, E>
+where
+    F: FnMut(&MatchUpdate) -> Result<(), E>,
+    E: From<EloError>,
+{
+    apply_interned(results, standings, games_played, configuration, Some(inspect))
+}
+
+/// Applies the matches to ratings indexed by interned team ids. An update, with its two names, is
+/// only built for `inspect` when there is one.
+fn apply_interned<F, E>(results: &[MatchResult], standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration, mut inspect: Option<F>) -> Result<Standings, E>
+where
+    F: FnMut(&MatchUpdate) -> Result<(), E>,
+    E: From<EloError>,
+{
+    if standings.is_empty() && configuration.default_rating.is_none() && results.iter().any(|result| configuration.counts_match(result)) {
+        return Err(EloError::NoTeams.into());
+    }
+
+    let mut teams = TeamIds::default();
+    let mut ratings: Vec<f32> = Vec::with_capacity(standings.len());
+    for (team, rating) in standings.iter() {
+        teams.intern(team);
+        ratings.push(*rating);
+    }
+    let matches: Vec<(usize, &MatchResult, usize, usize)> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, result)| configuration.counts_match(result))
+        .map(|(index, result)| {
+            let winner = teams.intern(configuration.canonical_team(&result.winner));
+            let loser = teams.intern(configuration.canonical_team(&result.loser));
+            (index, result, winner, loser)
+        })
+        .collect();
+    ratings.resize(teams.len(), configuration.default_rating());
+    let mut games: Vec<u32> = (0..teams.len()).map(|id| games_played.get(teams.name(id)).copied().unwrap_or(0)).collect();
+    let mut meetings: HashMap<(usize, usize), u32> = HashMap::new();
+
+    for (index, result, winner, loser) in matches {
+        if winner == loser {
+            return Err(EloError::SelfMatch(teams.name(winner).to_string()).in_match(index, result).into());
+        }
+        let team_state = |id: usize| TeamState { team: teams.name(id), rating: ratings[id], games: games[id] };
+        let previous_meetings = meetings.entry((winner.min(loser), winner.max(loser))).or_insert(0);
+        let rated = rate_match(result, team_state(winner), team_state(loser), *previous_meetings, configuration)
+            .map_err(|error| error.in_match(index, result))?;
+        *previous_meetings += 1;
+
+        ratings[winner] += rated.winner_delta;
+        ratings[loser] += rated.loser_delta;
+        games[winner] += 1;
+        games[loser] += 1;
+        if let Some(inspect) = inspect.as_mut() {
+            inspect(&rated.update(teams.name(winner), teams.name(loser), result.draw))?;
+        }
+    }
+
+    Ok(teams.into_names().into_iter().zip(ratings).collect())
+}
+
+/// Team names interned to dense ids, the index of the name in `names`.
+#[derive(Default)]
+struct TeamIds<'a> {
+    ids: HashMap<&'a str, usize>,
+    names: Vec<&'a str>,
+}
+
+impl<'a> TeamIds<'a> {
+    fn intern(&mut self, team: &'a str) -> usize {
+        let names = &mut self.names;
+        *self.ids.entry(team).or_insert_with(|| {
+            names.push(team);
+            names.len() - 1
+        })
+    }
+
+    fn name(&self, id: usize) -> &'a str {
+        self.names[id]
+    }
+
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    fn into_names(self) -> Vec<String> {
+        self.names.into_iter().map(str::to_string).collect()
+    }
+}
+
+/// Applies every match in order, calling `inspect` with each update and the standings after it.
+///
+/// Stops at the first match that cannot be applied, the same as `apply_match_results`, or at the
+/// first error returned by `inspect`.
+pub fn apply_match_results_inspect<F, E>(results: &[MatchResult], standings: &Standings, configuration: &Configuration, inspect: F) -> Result<Standings, E>
+where
+    F: FnMut(&MatchUpdate, &Standings) -> Result<(), E>,
+    E: From<EloError>,
+{
+    apply_match_results_inspect_with_games(results, standings, &GamesPlayed::new(), configuration, inspect)
+}
+
+/// Same as `apply_match_results_inspect` but starts from `games_played` games for each team
+/// rather than none, such as the games carried over from an earlier run.
+///
+/// Rated matches on empty standings are an error unless the configuration sets a
+/// `default_rating`, since every team would silently be created at 1500.
+pub fn apply_match_results_inspect_with_games<F, E>(results: &[MatchResult], standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration, mut inspect: F) -> Result<Standings, E>
+where
+    F: FnMut(&MatchUpdate, &Standings) -> Result<(), E>,
+    E: From<EloError>,
+{
+    if standings.is_empty() && configuration.default_rating.is_none() && results.iter().any(|result| configuration.counts_match(result)) {
+        return Err(EloError::NoTeams.into());
+    }
+
+    let mut new_standings = standings.clone();
+    let mut games_played = games_played.clone();
+    let mut meetings = Meetings::default();
+
+    for (index, result) in results.iter().enumerate().filter(|(_, result)| configuration.counts_match(result)) {
+        let update = get_match_update_with_meetings(result, &new_standings, &games_played, &meetings, configuration)
+            .map_err(|error| error.in_match(index, result))?;
+
+        update.apply_to(&mut new_standings);
+        update.record_games_played(&mut games_played);
+        if configuration.repeat_matchup_decay.is_some() {
+            meetings.record(&update);
+        }
+        inspect(&update, &new_standings)?;
+    }
+
+    Ok(new_standings)
+}
+
+/// Same as `apply_match_results_inspect_with_games` for matches that arrive one at a time, such
+/// as from `NdjsonMatches`, so they never all have to be in memory. Stops at the first match that
+/// cannot be read or applied.
+///
+/// Empty standings without a `default_rating` are only an error once a rated match arrives.
+pub fn apply_match_stream<I, F, E>(results: I, standings: &Standings, games_played: &GamesPlayed, configuration: &Configuration, mut inspect: F) -> Result<Standings, E>
+where
+    I: IntoIterator<Item = Result<MatchResult, EloError>>,
+    F: FnMut(&MatchUpdate, &Standings) -> Result<(), E>,
+    E: From<EloError>,
+{
+    let mut new_standings = standings.clone();
+    let mut games_played = games_played.clone();
+    let mut meetings = Meetings::default();
+
+    for (index, result) in results.into_iter().enumerate() {
+        let result = result?;
+        if !configuration.counts_match(&result) {
+            continue;
+        }
+        if new_standings.is_empty() && configuration.default_rating.is_none() {
+            return Err(EloError::NoTeams.into());
+        }
+
+        let update = get_match_update_with_meetings(&result, &new_standings, &games_played, &meetings, configuration)
+            .map_err(|error| error.in_match(index, &result))?;
+
+        update.apply_to(&mut new_standings);
+        update.record_games_played(&mut games_played);
+        if configuration.repeat_matchup_decay.is_some() {
+            meetings.record(&update);
+        }
+        inspect(&update, &new_standings)?;
+    }
+
+    Ok(new_standings)
+}
+
+/// Same as `apply_match_results` but writes a line describing every applied match to `log`.
+pub fn apply_match_results_with_log(results: &[MatchResult], standings: &Standings, configuration: &Configuration, log: &mut dyn Write) -> Result<Standings, Box<dyn Error>> {
+    apply_match_results_inspect(results, standings, configuration, |update, _| -> Result<(), Box<dyn Error>> {
+        writeln!(log, "{}", update)?;
+        Ok(())
+    })
+}
+
+/// Same as `apply_match_results` but returns the standings after every applied match, in order.
+pub fn apply_match_results_with_history(results: &[MatchResult], standings: &Standings, configuration: &Configuration) -> Result<Vec<Standings>, EloError> {
+    let mut history = Vec::with_capacity(results.len());
+
+    apply_match_results_inspect(results, standings, configuration, |_, new_standings| -> Result<(), EloError> {
+        history.push(new_standings.clone());
+        Ok(())
+    })?;
+
+    Ok(history)
+}
+
+/// Team names that do not line up between a set of matches and the standings they are applied to.
+#[derive(Debug, PartialEq)]
+pub struct TeamCrossReference {
+    /// Teams that appear in matches but not in the standings, sorted by name.
+    pub unknown_teams: Vec<String>,
+    /// Teams in the standings that do not appear in any match, sorted by name.
+    pub unplayed_teams: Vec<String>,
+}
+
+impl TeamCrossReference {
+    pub fn is_clean(&self) -> bool {
+        self.unknown_teams.is_empty() && self.unplayed_teams.is_empty()
+    }
+
+    /// One line per kind of mismatch found, empty when the names line up.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.unknown_teams.is_empty() {
+            warnings.push(format!("teams in matches but not in standings: {}", self.unknown_teams.join(", ")));
+        }
+        if !self.unplayed_teams.is_empty() {
+            warnings.push(format!("teams in standings with no matches: {}", self.unplayed_teams.join(", ")));
+        }
+
+        warnings
+    }
+}
+
+/// How problems that do not stop a run from rating its matches are handled, such as teams missing
+/// from the standings, matches of a team against itself or k brackets that do not start at 0.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Strictness {
+    /// Carry on without mentioning them.
+    Off,
+    /// Print each problem and carry on.
+    Warn,
+    /// Fail on the first run that has any.
+    Error,
+}
+
+impl Strictness {
+    /// The warnings to print for the problems found in a run, or an error listing all of them.
+    pub fn check(self, warnings: &[String]) -> Result<&[String], String> {
+        match self {
+            Strictness::Off => Ok(&[]),
+            Strictness::Warn => Ok(warnings),
+            Strictness::Error if warnings.is_empty() => Ok(&[]),
+            Strictness::Error => Err(warnings.join("; ")),
+        }
+    }
+}
+
+impl FromStr for Strictness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(Strictness::Off),
+            "warn" => Ok(Strictness::Warn),
+            "error" => Ok(Strictness::Error),
+            _ => Err(format!("invalid strictness `{}`, expected off, warn or error", s)),
+        }
+    }
+}
+
+/// A match as written in a matches file with its series kind left unread, so `check_matches` can
+/// report a bad series kind rather than fail on it.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct UncheckedMatch {
+    pub winner: String,
+    pub loser: String,
+    #[serde(default)]
+    pub series: serde_json::Value,
+    #[serde(default = "default_rated")]
+    pub rated: bool,
+}
+
+impl From<&MatchResult> for UncheckedMatch {
+    fn from(result: &MatchResult) -> Self {
+        UncheckedMatch {
+            winner: result.winner.clone(),
+            loser: result.loser.clone(),
+            series: serde_json::to_value(result.series).unwrap_or_default(),
+            rated: result.rated,
+        }
+    }
+}
+
+/// Reads matches like `parse_match_results_from_path` without reading their series kinds.
+pub fn parse_unchecked_matches_from_path(path: &Path) -> Result<Vec<UncheckedMatch>, EloError> {
+    if is_ndjson(path) {
+        return parse_unchecked_ndjson_matches_from_path(path);
+    }
+
+    if has_extension(path, "csv") {
+        let mut csv_reader = csv::Reader::from_reader(open_reader(path)?);
+        return csv_reader.deserialize().collect::<Result<_, _>>().map_err(|error| parse_error(path, error));
+    }
+
+    parse_type_from_path(path)
+}
+
+/// Reads the NDJSON matches at `path`, whatever its extension, without reading their series kinds.
+pub fn parse_unchecked_ndjson_matches_from_path(path: &Path) -> Result<Vec<UncheckedMatch>, EloError> {
+    let mut matches = Vec::new();
+    for (line_number, line) in open_reader(path)?.lines().enumerate() {
+        let line = line.map_err(|error| EloError::Io { path: path.to_path_buf(), source: error })?;
+        if !line.trim().is_empty() {
+            matches.push(serde_json::from_str(&line).map_err(|error| parse_error(path, format!("line {}: {}", line_number + 1, error)))?);
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Everything `check_matches` found wrong with a list of matches, each problem with the index of
+/// the first match it was found in.
+#[derive(Debug, Default, PartialEq)]
+pub struct MatchCheck {
+    pub matches: usize,
+    /// Teams that are not in the standings while the configuration has no `default_rating` to
+    /// create them with.
+    pub missing_teams: Vec<(usize, String)>,
+    /// Series kinds that cannot be read, as they were written.
+    pub invalid_series: Vec<(usize, String)>,
+    /// Teams that play themselves, after aliases.
+    pub self_matches: Vec<(usize, String)>,
+}
+
+impl MatchCheck {
+    pub fn problem_count(&self) -> usize {
+        self.missing_teams.len() + self.invalid_series.len() + self.self_matches.len()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.problem_count() == 0
+    }
+}
+
+impl fmt::Display for MatchCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Matches checked: {}", self.matches)?;
+        let classes = [
+            ("Missing teams", &self.missing_teams),
+            ("Invalid series kinds", &self.invalid_series),
+            ("Self matches", &self.self_matches),
+        ];
+        for (class, problems) in classes {
+            writeln!(f, "{}: {}", class, problems.len())?;
+            for (index, problem) in problems {
+                writeln!(f, "  match {}: {}", index, problem)?;
+            }
+        }
+        writeln!(f, "Result: {}", if self.passed() { "pass" } else { "fail" })
+    }
+}
+
+/// Checks that every match names teams that are in the standings, or can be created with the
+/// configured `default_rating`, a readable series kind and two different teams, without rating
+/// anything. Matches against ignored teams are skipped like they are by `update`, and unrated
+/// matches only have their series kind checked.
+pub fn check_matches(matches: &[UncheckedMatch], standings: &Standings, configuration: &Configuration) -> MatchCheck {
+    let mut check = MatchCheck { matches: matches.len(), ..MatchCheck::default() };
+    let mut missing = HashSet::new();
+
+    for (index, unchecked) in matches.iter().enumerate() {
+        let winner = configuration.canonical_team(&unchecked.winner);
+        let loser = configuration.canonical_team(&unchecked.loser);
+        if configuration.ignored_teams.contains(winner) || configuration.ignored_teams.contains(loser) {
+            continue;
+        }
+
+        if unchecked.rated && configuration.default_rating.is_none() {
+            for team in [winner, loser] {
+                if !standings.contains_key(team) && missing.insert(team) {
+                    check.missing_teams.push((index, team.to_string()));
+                }
+            }
+        }
+        if <SeriesKind as serde::Deserialize>::deserialize(&unchecked.series).is_err() {
+            check.invalid_series.push((index, unchecked.series.to_string()));
+        }
+        if unchecked.rated && winner == loser {
+            check.self_matches.push((index, winner.to_string()));
+        }
+    }
+
+    check
+}
+
+pub fn cross_reference_teams(results: &[MatchResult], standings: &Standings) -> TeamCrossReference {
+    cross_reference_team_names(results.iter().flat_map(|result| [result.winner.as_str(), result.loser.as_str()]), standings)
+}
+
+/// Like `cross_reference_teams`, but every team in the matches is first resolved through the
+/// aliases of `configuration`, so an alias of a team in the standings lines up with it.
+pub fn cross_reference_canonical_teams(results: &[MatchResult], standings: &Standings, configuration: &Configuration) -> TeamCrossReference {
+    let match_teams = results
+        .iter()
+        .flat_map(|result| [configuration.canonical_team(&result.winner), configuration.canonical_team(&result.loser)]);
+    cross_reference_team_names(match_teams, standings)
+}
+
+/// Like `cross_reference_teams` for the teams of matches that are no longer held, such as the
+/// names seen in a stream of matches.
+pub fn cross_reference_team_names<'a>(match_teams: impl Iterator<Item = &'a str>, standings: &Standings) -> TeamCrossReference {
+    let match_teams: HashSet<&str> = match_teams.collect();
+
+    let mut unknown_teams: Vec<String> = match_teams.iter().filter(|team| !standings.contains_key(**team)).map(|team| team.to_string()).collect();
+    unknown_teams.sort();
+
+    let mut unplayed_teams: Vec<String> = standings.keys().filter(|team| !match_teams.contains(team.as_str())).cloned().collect();
+    unplayed_teams.sort();
+
+    TeamCrossReference { unknown_teams, unplayed_teams }
+}
+
+pub fn get_probabilities_from_standings(standings: &Standings, team_a: &str, team_b: &str) -> Result<(f32, f32), EloError> {
+    let rating = |team: &str| standings.get(team).copied().ok_or_else(|| EloError::MissingTeam(team.to_string()));
+    let rating_a = rating(team_a)?;
+    let rating_b = rating(team_b)?;
+
+    Ok(get_expected_probabilities(rating_a, rating_b))
+}
+
+/// `matrix[a][b]` is the probability that team `a` beats team `b`, for every pair of teams in
+/// `standings`. A team's probability against itself is 0.5.
+pub type ProbabilityMatrix = HashMap<String, HashMap<String, f32>>;
+
+pub fn probability_matrix(standings: &Standings, scale: EloScale) -> ProbabilityMatrix {
+    standings
+        .iter()
+        .map(|(team_a, rating_a)| {
+            let row = standings
+                .iter()
+                .map(|(team_b, rating_b)| {
+                    let probability = if team_a == team_b { 0.5f32 } else { get_expected_probabilities_with_scale(*rating_a, *rating_b, scale).0 };
+                    (team_b.clone(), probability)
+                })
+                .collect();
+            (team_a.clone(), row)
+        })
+        .collect()
+}
+
+/// Whether the format extension of `path` is `extension`. A trailing `.gz` is skipped, so
+/// `matches.csv.gz` has the extension `csv`.
+pub(crate) fn has_extension(path: &Path, extension: &str) -> bool {
+    let format_path = match path.file_stem() {
+        Some(stem) if is_gzip(path) => Path::new(stem),
+        _ => path,
+    };
+    format_path.extension().is_some_and(|path_extension| path_extension.eq_ignore_ascii_case(extension))
+}
+
+/// Files ending in `.gz` are decompressed when read and compressed when written.
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|path_extension| path_extension.eq_ignore_ascii_case("gz"))
+}
+
+/// `-` stands for stdin when reading and for stdout when writing.
+fn is_standard_stream(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Opens `path` for reading, or stdin when the path is `-`. Gzipped files are decompressed.
+fn open_reader(path: &Path) -> Result<Box<dyn BufRead>, EloError> {
+    if is_standard_stream(path) {
+        return Ok(Box::new(BufReader::new(io::stdin())));
+    }
+
+    let file = File::open(path).map_err(|error| match error.kind() {
+        io::ErrorKind::NotFound => EloError::FileNotFound(path.to_path_buf()),
+        _ => EloError::Io { path: path.to_path_buf(), source: error },
+    })?;
+    if is_gzip(path) {
+        return Ok(Box::new(BufReader::new(GzDecoder::new(file))));
+    }
+    Ok(Box::new(BufReader::new(file)))
+}
+
+fn parse_error(path: &Path, source: impl Into<Box<dyn Error + Send + Sync>>) -> EloError {
+    EloError::ParseError { path: path.to_path_buf(), source: source.into() }
+}
+
+pub fn parse_type_from_path<'a, T>(path: &Path) -> Result<T, EloError> 
+where
+    for<'de> T: serde::Deserialize<'de> + 'a
+{
+    let mut reader = open_reader(path)?;
+
+    if has_extension(path, "toml") {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).map_err(|error| EloError::Io { path: path.to_path_buf(), source: error })?;
+        return toml::from_str(&contents).map_err(|error| parse_error(path, error));
+    }
+
+    if is_yaml(path) {
+        return serde_yaml::from_reader(reader).map_err(|error| parse_error(path, error));
+    }
+
+    serde_json::from_reader(reader).map_err(|error| parse_error(path, error))
+}
+
+/// Reads the config files at `paths` in order and merges them into one document, so a base
+/// config can be refined by partial override files. Later files override the fields of earlier
+/// ones. Tables, such as `team_k_overrides`, are merged key by key, while lists, including
+/// `k_brackets`, replace the earlier list whole.
+pub fn parse_layered_config_value(paths: &[&Path]) -> Result<serde_json::Value, EloError> {
+    if let [path] = paths {
+        return parse_type_from_path(path);
+    }
+
+    let mut merged = serde_json::Value::Object(serde_json::Map::new());
+    for path in paths {
+        // Read through a YAML value first, JSON values have no room for NaN and would quietly
+        // turn a non-finite number from a TOML or YAML file into null.
+        let layer: serde_yaml::Value = parse_type_from_path(path)?;
+        if has_non_finite_number(&layer) {
+            return Err(parse_error(path, "every number must be finite"));
+        }
+        let layer = serde_json::to_value(layer).map_err(|error| parse_error(path, error))?;
+        merge_config_layer(&mut merged, layer);
+    }
+    Ok(merged)
+}
+
+/// The environment variable `update` and the other commands read a config path from when no
+/// `--config` is given.
+pub const CONFIG_ENV_VAR: &str = "ELO_CONFIG";
+
+/// The config files to read, `flag_paths` when any were given with `--config`, otherwise the path
+/// in `env_path`, the value of `ELO_CONFIG`, unless it is empty. `None` when there is neither, in
+/// which case commands use `config.json` or no config at all.
+pub fn resolve_config_paths(flag_paths: Vec<PathBuf>, env_path: Option<OsString>) -> Option<Vec<PathBuf>> {
+    if !flag_paths.is_empty() {
+        return Some(flag_paths);
+    }
+
+    env_path.filter(|path| !path.is_empty()).map(|path| vec![PathBuf::from(path)])
+}
+
+/// The `Configuration` of the merged config files at `paths`, see `parse_layered_config_value`.
+pub fn parse_layered_config(paths: &[&Path]) -> Result<Configuration, EloError> {
+    if let [path] = paths {
+        return parse_type_from_path(path);
+    }
+
+    let merged = parse_layered_config_value(paths)?;
+    let layers: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+    serde_json::from_value(merged).map_err(|error| parse_error(Path::new(&layers.join(" + ")), error))
+}
+
+fn merge_config_layer(base: &mut serde_json::Value, layer: serde_json::Value) {
+    match (base, layer) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config_layer(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, layer) => *base = layer,
+    }
+}
+
+fn has_non_finite_number(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::Number(number) => number.as_f64().is_some_and(|number| !number.is_finite()),
+        serde_yaml::Value::Sequence(values) => values.iter().any(has_non_finite_number),
+        serde_yaml::Value::Mapping(mapping) => mapping.values().any(has_non_finite_number),
+        serde_yaml::Value::Tagged(tagged) => has_non_finite_number(&tagged.value),
+        _ => false,
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    has_extension(path, "yaml") || has_extension(path, "yml")
+}
+
+/// A team in a standings file, either just its rating or its rating along with the games played
+/// written by `--with-uncertainty`, a display name and a division. Any other fields, such as `rd`,
+/// are ignored.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum StandingsEntry {
+    Rating(f32),
+    Rated {
+        rating: f32,
+        #[serde(default)]
+        games: u32,
+        #[serde(default)]
+        display_name: Option<String>,
+        #[serde(default)]
+        division: Option<String>,
+    },
+}
+
+/// Everything a standings file records about its teams.
+#[derive(Debug, Default, PartialEq)]
+pub struct StandingsFile {
+    pub standings: Standings,
+    /// Only teams given in the extended form have games played.
+    pub games_played: GamesPlayed,
+    pub display_names: DisplayNames,
+    pub divisions: Divisions,
+}
+
+/// Reads standings in either the plain `team -> rating` form or the extended
+/// `team -> { rating, games }` form, ignoring any games played.
+pub fn parse_standings_from_path(path: &Path) -> Result<Standings, EloError> {
+    parse_standings_with_games_from_path(path).map(|(standings, _)| standings)
+}
+
+/// Reads standings like `parse_standings_from_path` along with the games played by every team in
+/// the extended form. Teams given as a plain rating have no games played.
+pub fn parse_standings_with_games_from_path(path: &Path) -> Result<(Standings, GamesPlayed), EloError> {
+    parse_standings_file_from_path(path).map(|file| (file.standings, file.games_played))
+}
+
+/// Reads standings in either form along with the games played, display names and divisions given
+/// in the extended form.
+pub fn parse_standings_file_from_path(path: &Path) -> Result<StandingsFile, EloError> {
+    let entries: HashMap<String, StandingsEntry> = parse_type_from_path(path)?;
+
+    let mut file = StandingsFile::default();
+    for (team, entry) in entries {
+        let rating = match entry {
+            StandingsEntry::Rating(rating) | StandingsEntry::Rated { rating, .. } => rating,
+        };
+        if !rating.is_finite() {
+            return Err(parse_error(path, format!("team '{}' has a rating of {}, ratings must be finite", team, rating)));
+        }
+
+        match entry {
+            StandingsEntry::Rating(rating) => {
+                file.standings.insert(team, rating);
+            },
+            StandingsEntry::Rated { rating, games, display_name, division } => {
+                if let Some(display_name) = display_name {
+                    file.display_names.insert(team.clone(), display_name);
+                }
+                if let Some(division) = division {
+                    file.divisions.insert(team.clone(), division);
+                }
+                file.games_played.insert(team.clone(), games);
+                file.standings.insert(team, rating);
+            },
+        }
+    }
+
+    Ok(file)
+}
+
+pub fn parse_match_results_from_path(path: &Path) -> Result<Vec<MatchResult>, EloError> {
+    if is_ndjson(path) {
+        return open_ndjson_matches(path)?.collect();
+    }
+
+    if has_extension(path, "csv") {
+        return parse_match_results_from_csv(open_reader(path)?).map_err(|error| parse_error(path, error));
+    }
+
+    parse_type_from_path(path)
+}
+
+/// Whether `path` holds newline delimited JSON matches, judging by its `.ndjson` extension.
+pub fn is_ndjson(path: &Path) -> bool {
+    has_extension(path, "ndjson")
+}
+
+/// Match results read from newline delimited JSON, one `MatchResult` object per line. Each line is
+/// only read and parsed when the next match is asked for, and blank lines are skipped.
+pub struct NdjsonMatches<R> {
+    lines: io::Lines<R>,
+    path: PathBuf,
+    line: usize,
+}
+
+impl<R: BufRead> NdjsonMatches<R> {
+    /// `path` is only used in error messages.
+    pub fn new(reader: R, path: &Path) -> Self {
+        NdjsonMatches { lines: reader.lines(), path: path.to_path_buf(), line: 0 }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonMatches<R> {
+    type Item = Result<MatchResult, EloError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(EloError::Io { path: self.path.clone(), source: error })),
+            };
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(serde_json::from_str(&line).map_err(|error| parse_error(&self.path, format!("line {}: {}", self.line, error))));
+        }
+    }
+}
+
+/// Opens the NDJSON matches at `path`, which can be `-` for stdin or gzipped like any other input.
+pub fn open_ndjson_matches(path: &Path) -> Result<NdjsonMatches<Box<dyn BufRead>>, EloError> {
+    Ok(NdjsonMatches::new(open_reader(path)?, path))
+}
+
+/// Parses match results from CSV with a `winner,loser,series` header.
+pub fn parse_match_results_from_csv(reader: impl Read) -> Result<Vec<MatchResult>, Box<dyn Error + Send + Sync>> {
+    let mut csv_reader = csv::Reader::from_reader(reader);
+    let headers = csv_reader.headers()?.clone();
+
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+            .ok_or(format!("missing `{}` column", name))
+    };
+    let winner_column = column("winner")?;
+    let loser_column = column("loser")?;
+    let series_column = column("series")?;
+
+    let mut results = Vec::new();
+    for record in csv_reader.records() {
+        let record = record?;
+        let row = record.position().map_or(0, |position| position.line());
+
+        let series_string = record.get(series_column).unwrap_or("");
+        let series = series_string
+            .parse()
+            .map_err(|error| format!("row {}: {}", row, error))?;
+
+        results.push(MatchResult {
+            winner: record.get(winner_column).unwrap_or("").to_string(),
+            loser: record.get(loser_column).unwrap_or("").to_string(),
+            series,
+            ..Default::default()
+        });
+    }
+
+    Ok(results)
+}
+
+/// Serializes `data` as pretty JSON with the keys of every map sorted, so the same data always
+/// produces the same output.
+pub fn to_sorted_json_string<T: serde::Serialize>(data: &T) -> Result<String, Box<dyn Error>> {
+    // `serde_json::Map` keeps its keys sorted, going through a `Value` sorts the keys of any
+    // `HashMap` in `data`.
+    let value = serde_json::to_value(data)?;
+    Ok(serde_json::to_string_pretty(&value)?)
+}
+
+/// Writes `data` to `path` as YAML if the extension is `.yaml` or `.yml` and pretty JSON otherwise.
+///
+/// A path of `-` writes the JSON to stdout followed by a newline.
+pub fn write_type_to_path<T: serde::Serialize>(path: &Path, data: &T) -> Result<(), Box<dyn Error>> {
+    let mut writer = create_writer(path)?;
+
+    if is_yaml(path) {
+        serde_yaml::to_writer(writer, &serde_json::to_value(data)?)?;
+        return Ok(());
+    }
+
+    let data_string = to_sorted_json_string(data)?;
+    writer.write_all(data_string.as_bytes())?;
+    if is_standard_stream(path) {
+        writer.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+/// Creates the file at `path` for writing, or writes to stdout when the path is `-`. Files
+/// ending in `.gz` are gzipped, the stream is finished when the writer is dropped.
+fn create_writer(path: &Path) -> io::Result<Box<dyn Write>> {
+    if is_standard_stream(path) {
+        return Ok(Box::new(io::stdout()));
+    }
+
+    let file = File::create(path)?;
+    if is_gzip(path) {
+        return Ok(Box::new(GzEncoder::new(file, Compression::default())));
+    }
+    Ok(Box::new(file))
+}
+
+pub fn write_standings_to_path(path: &Path, standings: &Standings) -> Result<(), Box<dyn Error>> {
+    write_type_to_path(path, standings)
+}
+
+/// Writes every team in the extended form `--with-uncertainty` writes, so the games played,
+/// display names and divisions read by `parse_standings_file_from_path` are kept.
+pub fn write_standings_file_to_path(path: &Path, file: &StandingsFile) -> Result<(), Box<dyn Error>> {
+    write_type_to_path(path, &standings_with_uncertainty(&file.standings, &file.games_played, &file.display_names, &file.divisions))
+}
+
+/// Rating deviation of a team that has not played any games yet.
+pub const INITIAL_RATING_DEVIATION: f32 = 350f32;
+/// Rating deviation never shrinks below this, however many games are played.
+pub const MIN_RATING_DEVIATION: f32 = 30f32;
+
+/// A simple measure of how uncertain a rating is, `350 / sqrt(1 + games)` with a floor of 30.
+///
+/// It starts at the rating deviation Glicko gives an unrated player and halves by the third game,
+/// so a rating backed by many games is trusted much more than one backed by a handful.
+pub fn rating_deviation(games: u32) -> f32 {
+    (INITIAL_RATING_DEVIATION / (1f32 + games as f32).sqrt()).max(MIN_RATING_DEVIATION)
+}
+
+/// A rating together with how many games back it and how uncertain it is.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct RatedTeam {
+    pub rating: f32,
+    pub games: u32,
+    pub rd: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub division: Option<String>,
+}
+
+/// Every team as a `RatedTeam`, keeping the display names and divisions so they survive into the
+/// next run.
+pub fn standings_with_uncertainty(standings: &Standings, games_played: &GamesPlayed, display_names: &DisplayNames, divisions: &Divisions) -> HashMap<String, RatedTeam> {
+    standings
+        .iter()
+        .map(|(team, rating)| {
+            let games = games_played.get(team).copied().unwrap_or(0);
+            let display_name = display_names.get(team).cloned();
+            let division = divisions.get(team).cloned();
+            (team.clone(), RatedTeam { rating: *rating, games, rd: rating_deviation(games), display_name, division })
+        })
+        .collect()
+}
+
+/// A team's rating before and after a run.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RatingChange {
+    pub old: f32,
+    pub new: f32,
+    pub delta: f32,
+}
+
+impl RatingChange {
+    /// The change with all three ratings rounded to `places` decimal places. The delta is
+    /// worked out again from the rounded ratings, so `old + delta` still rounds to `new`.
+    pub fn rounded(&self, places: u32) -> RatingChange {
+        let old = round_to_places(self.old, places);
+        let new = round_to_places(self.new, places);
+        RatingChange { old, new, delta: round_to_places(new - old, places) }
+    }
+}
+
+/// Every team of `after` with its rating in `before` next to the new one. Teams that were not in
+/// `before` are new in this run and count as starting from the configured `default_rating`.
+pub fn standings_with_previous(before: &Standings, after: &Standings, configuration: &Configuration) -> HashMap<String, RatingChange> {
+    after
+        .iter()
+        .map(|(team, rating)| {
+            let old = before.get(team).copied().unwrap_or(configuration.default_rating());
+            (team.clone(), RatingChange { old, new: *rating, delta: rating - old })
+        })
+        .collect()
+}
+
+/// Parses a list of team names, either as a JSON array of strings or as one name per line with
+/// blank lines ignored.
+pub fn parse_team_list(contents: &str) -> Result<Vec<String>, serde_json::Error> {
+    if contents.trim_start().starts_with('[') {
+        return serde_json::from_str(contents);
+    }
+
+    Ok(contents.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect())
+}
+
+/// Creates standings with every team at `default_rating`.
+pub fn standings_from_team_list(teams: &[String], default_rating: f32) -> Standings {
+    teams.iter().map(|team| (team.clone(), default_rating)).collect()
+}
+
+/// Wins and losses of a team before it was rated.
+pub type WinLossRecord = (u32, u32);
+
+/// Seeds standings from win-loss records, `default_rating + scale * (win_rate - 0.5)`.
+///
+/// A team that won every game starts `scale / 2` above the default and one that lost every game
+/// `scale / 2` below it. Teams without any games start at the default.
+pub fn standings_from_records(records: &HashMap<String, WinLossRecord>, default_rating: f32, scale: f32) -> Standings {
+    records
+        .iter()
+        .map(|(team, (wins, losses))| {
+            let games = wins + losses;
+            let win_rate = if games == 0 { 0.5f32 } else { *wins as f32 / games as f32 };
+            (team.clone(), default_rating + scale * (win_rate - 0.5f32))
+        })
+        .collect()
+}
+
+/// Rounds every rating to `places` decimal places, for output meant to be read by people.
+pub fn round_standings(standings: &Standings, places: u32) -> Standings {
+    standings
+        .iter()
+        .map(|(team, rating)| (team.clone(), round_to_places(*rating, places)))
+        .collect()
+}
+
+fn round_to_places(rating: f32, places: u32) -> f32 {
+    let factor = 10f64.powi(places as i32);
+    ((rating as f64 * factor).round() / factor) as f32
+}
+
+pub fn mean_rating(standings: &Standings) -> Option<f32> {
+    if standings.is_empty() {
+        return None;
+    }
+
+    Some(standings.values().sum::<f32>() / standings.len() as f32)
+}
+
+/// Shifts every rating by the same amount so the mean rating is `target_mean`, such as to undo the
+/// drift of many seasons. The differences between ratings are unchanged, and with them every
+/// expected probability.
+pub fn normalize_standings(standings: &Standings, target_mean: f32) -> Standings {
+    let shift = match mean_rating(standings) {
+        Some(mean) => target_mean - mean,
+        None => return Standings::new(),
+    };
+
+    standings.iter().map(|(team, rating)| (team.clone(), rating + shift)).collect()
+}
+
+/// Moves the rating of every team not in `active_teams` a fraction `factor` of the way toward
+/// the mean rating, `r' = r + factor * (mean - r)`.
+///
+/// Decaying only part of the pool would shift the mean, so afterwards every team, active or not,
+/// is shifted by the same amount to bring the mean back to where it was. When every team is
+/// inactive that shift is zero.
+pub fn decay_standings(standings: &Standings, factor: f32, active_teams: &HashSet<String>) -> Standings {
+    let mean = match mean_rating(standings) {
+        Some(mean) => mean,
+        None => return Standings::new(),
+    };
+
+    let mut decayed: Standings = standings
+        .iter()
+        .map(|(team, rating)| {
+            if active_teams.contains(team) {
+                (team.clone(), *rating)
+            } else {
+                (team.clone(), rating + factor * (mean - rating))
+            }
+        })
+        .collect();
+
+    let shift = mean - mean_rating(&decayed).unwrap();
+    for rating in decayed.values_mut() {
+        *rating += shift;
+    }
+
+    decayed
+}
+
+/// How `merge_standings` combines the ratings of a team found in both standings.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MergeStrategy {
+    /// The mean of the two ratings.
+    Average,
+    /// The higher of the two ratings.
+    Max,
+    /// The mean of the two ratings weighted by the games played behind each.
+    WeightedByGames,
+}
+
+impl FromStr for MergeStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "average" => Ok(MergeStrategy::Average),
+            "max" => Ok(MergeStrategy::Max),
+            "weighted-by-games" => Ok(MergeStrategy::WeightedByGames),
+            _ => Err(format!("invalid merge strategy `{}`, expected average, max or weighted-by-games", s)),
+        }
+    }
+}
+
+/// Combines two standings, such as those of two divisions that share some teams. Teams in only
+/// one of them are kept as they are, teams in both get a rating combined by `strategy`.
+///
+/// The games played by a team in both are added up. `WeightedByGames` falls back to the plain
+/// average for a team without games in either. Display names and divisions from `first` win over
+/// `second`.
+pub fn merge_standings(first: &StandingsFile, second: &StandingsFile, strategy: MergeStrategy) -> StandingsFile {
+    let games = |file: &StandingsFile, team: &String| file.games_played.get(team).copied().unwrap_or(0);
+    let mut merged = StandingsFile {
+        standings: second.standings.clone(),
+        games_played: second.games_played.clone(),
+        display_names: second.display_names.clone(),
+        divisions: second.divisions.clone(),
+    };
+
+    for (team, rating) in first.standings.iter() {
+        let combined = match second.standings.get(team) {
+            None => *rating,
+            Some(other) => match strategy {
+                MergeStrategy::Average => (rating + other) / 2f32,
+                MergeStrategy::Max => rating.max(*other),
+                MergeStrategy::WeightedByGames => {
+                    let (first_games, second_games) = (games(first, team), games(second, team));
+                    if first_games + second_games == 0 {
+                        (rating + other) / 2f32
+                    } else {
+                        (rating * first_games as f32 + other * second_games as f32) / (first_games + second_games) as f32
+                    }
+                },
+            },
+        };
+        merged.standings.insert(team.clone(), combined);
+        if let Some(first_games) = first.games_played.get(team) {
+            merged.games_played.insert(team.clone(), first_games + games(second, team));
+        }
+    }
+    merged.display_names.extend(first.display_names.iter().map(|(team, name)| (team.clone(), name.clone())));
+    merged.divisions.extend(first.divisions.iter().map(|(team, division)| (team.clone(), division.clone())));
+
+    merged
+}
+
+/// Returns the standings ordered by rating, highest first, with ties broken alphabetically.
+pub fn sorted_standings(standings: &Standings) -> Vec<(&String, f32)> {
+    sorted_standings_with_games(standings, &GamesPlayed::new())
+}
+
+/// Returns the standings ordered by rating, highest first. Teams with equal ratings are ordered by
+/// games played, most first, and then alphabetically.
+pub fn sorted_standings_with_games<'a>(standings: &'a Standings, games_played: &GamesPlayed) -> Vec<(&'a String, f32)> {
+    let games = |team: &String| games_played.get(team).copied().unwrap_or(0);
+    let mut sorted: Vec<(&String, f32)> = standings.iter().map(|(team, rating)| (team, *rating)).collect();
+    sorted.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| games(b.0).cmp(&games(a.0))).then_with(|| a.0.cmp(b.0)));
+
+    sorted
+}
+
+/// The teams of the standings tagged with `division`, teams without a division are left out.
+pub fn filter_by_division(standings: &Standings, divisions: &Divisions, division: &str) -> Standings {
+    standings
+        .iter()
+        .filter(|(team, _)| divisions.get(*team).is_some_and(|team_division| team_division == division))
+        .map(|(team, rating)| (team.clone(), *rating))
+        .collect()
+}
+
+/// The `count` highest rated teams of the standings, ordered like `sorted_standings_with_games`
+/// so ties at the cut are broken the same way as on the leaderboard.
+pub fn top_standings(standings: &Standings, games_played: &GamesPlayed, count: usize) -> Standings {
+    sorted_standings_with_games(standings, games_played)
+        .into_iter()
+        .take(count)
+        .map(|(team, rating)| (team.clone(), rating))
+        .collect()
+}
+
+/// The 1-based position of `team` among all teams by rating, highest first, or `None` when it is
+/// not in the standings.
+///
+/// Ties use standard competition ranking: teams with equal ratings share the best position
+/// between them and the positions they take up are skipped, so four teams ranked with a tie for
+/// second are ranked 1, 2, 2, 4. The rank is one more than the number of teams rated strictly
+/// higher. Unlike the leaderboard, ties are not broken by games played or name.
+///
+/// ```
+/// use elo::{rank_of, Standings};
+///
+/// let standings: Standings = [("C9", 1600.0), ("TSM", 1500.0), ("CLG", 1500.0), ("TL", 1400.0)]
+///     .iter()
+///     .map(|(team, rating)| (team.to_string(), *rating))
+///     .collect();
+/// assert_eq!(rank_of(&standings, "TSM"), Some(2));
+/// assert_eq!(rank_of(&standings, "CLG"), Some(2));
+/// assert_eq!(rank_of(&standings, "TL"), Some(4));
+/// assert_eq!(rank_of(&standings, "FLY"), None);
+/// ```
+pub fn rank_of(standings: &Standings, team: &str) -> Option<usize> {
+    let rating = *standings.get(team)?;
+    Some(1 + standings.values().filter(|other| **other > rating).count())
+}
+
+/// Formats the standings as a ranked table of rank, team and rating rounded to one decimal.
+pub fn format_leaderboard(standings: &Standings) -> String {
+    format_leaderboard_with_games(standings, &GamesPlayed::new())
+}
+
+/// Formats the standings like `format_leaderboard`, breaking rating ties like
+/// `sorted_standings_with_games`.
+pub fn format_leaderboard_with_games(standings: &Standings, games_played: &GamesPlayed) -> String {
+    format_leaderboard_with_names(standings, games_played, &DisplayNames::new(), 0)
+}
+
+/// Formats the standings like `format_leaderboard_with_games`, showing teams by their display
+/// name when they have one. Ties are still broken by the team key.
+///
+/// Teams that have played fewer than `min_games` are not ranked, they are listed in the same
+/// order in a separate section after the ranked teams.
+pub fn format_leaderboard_with_names(standings: &Standings, games_played: &GamesPlayed, display_names: &DisplayNames, min_games: u32) -> String {
+    let sorted = sorted_standings_with_games(standings, games_played);
+    let name_width = sorted.iter().map(|(team, _)| display_name(team, display_names).len()).max().unwrap_or(0).max("Team".len());
+    let (ranked, unranked): (Vec<_>, Vec<_>) = sorted
+        .into_iter()
+        .partition(|(team, _)| games_played.get(*team).copied().unwrap_or(0) >= min_games);
+
+    let mut leaderboard = format!("{:>4}  {:<width$}  {:>8}\n", "Rank", "Team", "Rating", width = name_width);
+    for (index, (team, rating)) in ranked.iter().enumerate() {
+        leaderboard.push_str(&format!("{:>4}  {:<width$}  {:>8.1}\n", index + 1, display_name(team, display_names), rating, width = name_width));
+    }
+    if !unranked.is_empty() {
+        leaderboard.push_str(&format!("Unranked, fewer than {} games:\n", min_games));
+        for (team, rating) in unranked.iter() {
+            leaderboard.push_str(&format!("{:>4}  {:<width$}  {:>8.1}\n", "-", display_name(team, display_names), rating, width = name_width));
+        }
+    }
+
+    leaderboard
+}
+
+/// Returns how much each team in `after` moved from `before`, largest gain first with ties broken
+/// alphabetically. Teams missing from `before` are compared against `default_rating`.
+pub fn rating_deltas<'a>(before: &Standings, after: &'a Standings, default_rating: f32) -> Vec<(&'a String, f32)> {
+    let mut deltas: Vec<(&String, f32)> = after
+        .iter()
+        .map(|(team, rating)| (team, rating - before.get(team).copied().unwrap_or(default_rating)))
+        .collect();
+    deltas.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    deltas
+}
+
+/// Formats the `count` biggest risers and fallers between two standings.
+pub fn format_movers(before: &Standings, after: &Standings, default_rating: f32, count: usize) -> String {
+    let deltas = rating_deltas(before, after, default_rating);
+
+    let mut movers = String::from("Risers:\n");
+    for (team, delta) in deltas.iter().filter(|(_, delta)| *delta > 0f32).take(count) {
+        movers.push_str(&format!("  {} {:+.1}\n", team, delta));
+    }
+    movers.push_str("Fallers:\n");
+    for (team, delta) in deltas.iter().rev().filter(|(_, delta)| *delta < 0f32).take(count) {
+        movers.push_str(&format!("  {} {:+.1}\n", team, delta));
+    }
+
+    movers
+}
+
+/// Totals describing what a run of `update` did.
+#[derive(Debug, PartialEq)]
+pub struct RunSummary {
+    pub matches_applied: usize,
+    /// Teams that played at least one of the matches.
+    pub teams_affected: usize,
+    /// Teams that were not in the standings before the run.
+    pub new_teams: usize,
+    pub highest: Option<(String, f32)>,
+    pub lowest: Option<(String, f32)>,
+    pub mean: Option<f32>,
+}
+
+/// Only the matches `configuration` counts are summarized, the same as in `build_run_report`, and
+/// aliases count as the team they stand for.
+pub fn summarize_run(before: &Standings, after: &Standings, results: &[MatchResult], configuration: &Configuration) -> RunSummary {
+    let rated: Vec<&MatchResult> = results.iter().filter(|result| configuration.counts_match(result)).collect();
+    let teams_affected: HashSet<&str> = rated
+        .iter()
+        .flat_map(|result| [configuration.canonical_team(&result.winner), configuration.canonical_team(&result.loser)])
+        .collect();
+    let sorted = sorted_standings(after);
+
+    RunSummary {
+        matches_applied: rated.len(),
+        teams_affected: teams_affected.len(),
+        new_teams: after.keys().filter(|team| !before.contains_key(*team)).count(),
+        highest: sorted.first().map(|(team, rating)| (team.to_string(), *rating)),
+        lowest: sorted.last().map(|(team, rating)| (team.to_string(), *rating)),
+        mean: mean_rating(after),
+    }
+}
+
+/// How a single team's rating changed over a run.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+pub struct TeamReport {
+    /// `None` for teams that were not in the standings before the run.
+    pub before: Option<f32>,
+    pub after: f32,
+    /// `after - before`, measured from the default rating for new teams.
+    pub delta: f32,
+    /// Games played, including those read from the standings.
+    pub games: u32,
+    /// Matches won during the run.
+    pub wins: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+/// Everything about a run of `update`, meant to be read by other programs.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct RunReport {
+    pub configuration: Configuration,
+    pub matches_applied: usize,
+    pub teams: HashMap<String, TeamReport>,
+    pub new_teams: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+pub fn build_run_report(
+    configuration: &Configuration,
+    before: &Standings,
+    after: &Standings,
+    results: &[MatchResult],
+    games_played: &GamesPlayed,
+    wins: &Wins,
+    warnings: Vec<String>,
+) -> RunReport {
+    let teams = after
+        .iter()
+        .map(|(team, rating)| {
+            let previous = before.get(team).copied();
+            let delta = rating - previous.unwrap_or(configuration.default_rating());
+            let games = games_played.get(team).copied().unwrap_or(0);
+            let wins = wins.get(team).copied().unwrap_or(0);
+            (team.clone(), TeamReport { before: previous, after: *rating, delta, games, wins, display_name: None })
+        })
+        .collect();
+    let mut new_teams: Vec<String> = after.keys().filter(|team| !before.contains_key(*team)).cloned().collect();
+    new_teams.sort();
+
+    RunReport {
+        configuration: configuration.clone(),
+        matches_applied: results.iter().filter(|result| configuration.counts_match(result)).count(),
+        teams,
+        new_teams,
+        warnings,
+    }
+}
+
+/// A team whose rating differs between computed standings and reference standings.
+#[derive(Debug, PartialEq)]
+pub struct RatingMismatch {
+    pub team: String,
+    /// `None` when the team is missing from that side.
+    pub computed: Option<f32>,
+    pub reference: Option<f32>,
+}
+
+impl fmt::Display for RatingMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.computed, self.reference) {
+            (Some(computed), Some(reference)) => write!(f, "{}: computed {:.1}, reference {:.1} ({:+.1})", self.team, computed, reference, computed - reference),
+            (Some(computed), None) => write!(f, "{}: computed {:.1}, missing from the reference", self.team, computed),
+            (None, Some(reference)) => write!(f, "{}: reference {:.1}, missing from the computed standings", self.team, reference),
+            (None, None) => write!(f, "{}: missing from both standings", self.team),
+        }
+    }
+}
+
+/// Every team whose rating in `computed` differs from `reference` by more than `tolerance`,
+/// sorted by team name. Teams that only appear on one side always count as a mismatch.
+pub fn compare_standings(computed: &Standings, reference: &Standings, tolerance: f32) -> Vec<RatingMismatch> {
+    let teams: HashSet<&String> = computed.keys().chain(reference.keys()).collect();
+    let mut mismatches: Vec<RatingMismatch> = teams
+        .into_iter()
+        .map(|team| RatingMismatch { team: team.clone(), computed: computed.get(team).copied(), reference: reference.get(team).copied() })
+        .filter(|mismatch| match (mismatch.computed, mismatch.reference) {
+            (Some(computed), Some(reference)) => (computed - reference).abs() > tolerance,
+            _ => true,
+        })
+        .collect();
+    mismatches.sort_by(|a, b| a.team.cmp(&b.team));
+    mismatches
+}
+
+impl RunReport {
+    /// Adds the display name of every team that has one.
+    pub fn with_display_names(mut self, display_names: &DisplayNames) -> Self {
+        for (team, team_report) in self.teams.iter_mut() {
+            team_report.display_name = display_names.get(team).cloned();
+        }
+        self
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Matches applied: {}", self.matches_applied)?;
+        writeln!(f, "Teams affected: {}", self.teams_affected)?;
+        writeln!(f, "New teams: {}", self.new_teams)?;
+        if let Some((team, rating)) = self.highest.as_ref() {
+            writeln!(f, "Highest rating: {} ({:.1})", team, rating)?;
+        }
+        if let Some((team, rating)) = self.lowest.as_ref() {
+            writeln!(f, "Lowest rating: {} ({:.1})", team, rating)?;
+        }
+        if let Some(mean) = self.mean {
+            writeln!(f, "Mean rating: {:.1}", mean)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn write_standings_csv(path: &Path, standings: &Standings) -> Result<(), Box<dyn Error>> {
+    write_standings_csv_to_writer(create_writer(path)?, standings)
+}
+
+/// Writes standings as CSV with `team,rating` columns, sorted by rating descending.
+pub fn write_standings_csv_to_writer(writer: impl Write, standings: &Standings) -> Result<(), Box<dyn Error>> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(["team", "rating"])?;
+
+    for (team, rating) in sorted_standings(standings) {
+        csv_writer.write_record([team.as_str(), rating.to_string().as_str()])?;
+    }
+    csv_writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_brackets() -> Vec<KBracket> {
+        vec![
+            KBracket { start: 0, k: 40f32 },
+            KBracket { start: 2400, k: 10f32 },
+            KBracket { start: 1200, k: 20f32 },
+        ]
+    }
+
+    fn test_config() -> Configuration {
+        Configuration {
+            bo1_score: 1f32,
+            bo3_score: 1.25f32,
+            bo5_score: 1.5f32,
+            bo7_score: None,
+            custom_scores: HashMap::new(),
+            series_margin_scores: HashMap::new(),
+            k_brackets: test_brackets(),
+            k_selection: KSelection::Average,
+            bracket_interpolation: false,
+            default_rating: None,
+            rating_floor: None,
+            rating_ceiling: None,
+            ceiling_compression: None,
+            max_delta_per_match: None,
+            team_k_overrides: HashMap::new(),
+            aliases: HashMap::new(),
+            ignored_teams: HashSet::new(),
+            provisional_games: 0,
+            provisional_k: None,
+            leaderboard_min_games: 0,
+            home_advantage: None,
+            upset_factor: None,
+            repeat_matchup_decay: None,
+            elo_base: 10f32,
+            elo_divisor: 400f32,
+        }
+    }
+
+    fn test_match(winner: &str, loser: &str) -> MatchResult {
+        MatchResult {
+            winner: winner.to_string(),
+            loser: loser.to_string(),
+            series: SeriesKind::Bo1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scaling_for_rating_picks_highest_matching_bracket() {
+        let k_brackets = test_brackets();
+
+        assert_eq!(scaling_for_rating(2500f32, &k_brackets), Some(10f32));
+        assert_eq!(scaling_for_rating(2400f32, &k_brackets), Some(10f32));
+        assert_eq!(scaling_for_rating(1500f32, &k_brackets), Some(20f32));
+        assert_eq!(scaling_for_rating(800f32, &k_brackets), Some(40f32));
+        assert_eq!(scaling_for_rating(0f32, &k_brackets), Some(40f32));
+    }
+
+    #[test]
+    fn unknown_teams_start_at_default_rating() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+
+        let results = vec![test_match("C9", "NEW")];
+        let new_standings = apply_match_results(&results, &standings, &test_config()).unwrap();
+
+        assert_eq!(new_standings.len(), 2);
+        assert!(new_standings["C9"] > 1500f32);
+        assert!(new_standings["NEW"] < 1500f32);
+    }
+
+    #[test]
+    fn default_rating_falls_back_to_1500() {
+        let config: Configuration = serde_json::from_str(
+            r#"{"bo1_score": 1, "bo3_score": 1, "bo5_score": 1, "k_brackets": [{"start": 0, "k": 32}]}"#,
+        ).unwrap();
+
+        assert_eq!(config.default_rating(), 1500f32);
+    }
+
+    #[test]
+    fn parses_match_results_from_csv() {
+        let csv = "winner,loser,series\nC9,TSM,bo1\nTL,CLG,Bo5\n";
+        let results = parse_match_results_from_csv(csv.as_bytes()).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].winner, "TL");
+        assert_eq!(results[1].loser, "CLG");
+        assert!(matches!(results[1].series, SeriesKind::Bo5));
+    }
+
+    #[test]
+    fn csv_invalid_series_names_row() {
+        let csv = "winner,loser,series\nC9,TSM,bo1\nTL,CLG,final\n";
+        let error = parse_match_results_from_csv(csv.as_bytes()).err().unwrap();
+
+        assert!(error.to_string().starts_with("row 3:"), "{}", error);
+    }
+
+    #[test]
+    fn standings_csv_is_sorted_and_quoted() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1400f32);
+        standings.insert("Team, Liquid".to_string(), 1600f32);
+
+        let mut output = Vec::new();
+        write_standings_csv_to_writer(&mut output, &standings).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "team,rating\n\"Team, Liquid\",1600\nC9,1400\n");
+    }
+
+    #[test]
+    fn leaderboard_breaks_ties_alphabetically() {
+        let mut standings = Standings::new();
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TL".to_string(), 1612.34f32);
+
+        let leaderboard = format_leaderboard(&standings);
+        let lines: Vec<&str> = leaderboard.lines().collect();
+
+        assert_eq!(lines[1], "   1  TL      1612.3");
+        assert_eq!(lines[2], "   2  C9      1500.0");
+        assert_eq!(lines[3], "   3  TSM     1500.0");
+    }
+
+    #[test]
+    fn sweep_moves_ratings_more_than_close_series() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+
+        let unscored = test_match("C9", "TSM");
+        let close = MatchResult { series: SeriesKind::Bo3, winner_games: Some(2), loser_games: Some(1), ..test_match("C9", "TSM") };
+        let sweep = MatchResult { series: SeriesKind::Bo3, winner_games: Some(2), loser_games: Some(0), ..test_match("C9", "TSM") };
+
+        let config = test_config();
+        let unscored_update = get_match_update(&unscored, &standings, &GamesPlayed::new(), &config).unwrap();
+        let close_update = get_match_update(&close, &standings, &GamesPlayed::new(), &config).unwrap();
+        let sweep_update = get_match_update(&sweep, &standings, &GamesPlayed::new(), &config).unwrap();
+
+        assert_eq!(close_update.winner_k, unscored_update.winner_k);
+        assert!(sweep_update.winner_delta > close_update.winner_delta);
+        assert!(sweep_update.loser_delta < close_update.loser_delta);
+    }
+
+    #[test]
+    fn margin_multiplier_is_capped() {
+        assert_eq!(margin_multiplier(None, None), 1f32);
+        assert_eq!(margin_multiplier(Some(3), Some(2)), 1f32);
+        assert_eq!(margin_multiplier(Some(2), Some(0)), 1.5f32);
+        assert!(margin_multiplier(Some(100), Some(0)) < 2f32);
+    }
+
+    #[test]
+    fn history_has_a_snapshot_per_match() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+
+        let results = vec![test_match("C9", "TSM"), test_match("TSM", "C9"), test_match("C9", "TSM")];
+        let config = test_config();
+        let history = apply_match_results_with_history(&results, &standings, &config).unwrap();
+
+        assert_eq!(history.len(), 3);
+        assert!(history[0]["C9"] > 1500f32);
+        assert_eq!(history[2], apply_match_results(&results, &standings, &config).unwrap());
+    }
+
+    #[test]
+    fn valid_config_passes_validation() {
+        assert!(test_config().validate().is_ok());
+    }
+
+    #[test]
+    fn validation_rejects_empty_brackets() {
+        let config = Configuration { k_brackets: vec![], ..test_config() };
+        let problems = config.validate().unwrap_err().problems;
+
+        assert_eq!(problems, vec!["k_brackets must not be empty"]);
+    }
+
+    #[test]
+    fn brackets_not_covering_zero_warn_and_use_the_lowest_bracket() {
+        let config = Configuration {
+            k_brackets: vec![KBracket { start: 1000, k: 32f32 }, KBracket { start: 2000, k: 16f32 }],
+            ..test_config()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.warnings(), vec!["the lowest k bracket starts at 1000, ratings below it use its k of 32"]);
+        assert!(test_config().warnings().is_empty());
+
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 300f32);
+        standings.insert("TSM".to_string(), 300f32);
+        let new_standings = apply_match_results(&[test_match("C9", "TSM")], &standings, &config).unwrap();
+
+        assert_eq!(new_standings["C9"], 316f32);
+        assert_eq!(new_standings["TSM"], 284f32);
+    }
+
+    #[test]
+    fn validation_rejects_non_positive_k() {
+        let config = Configuration {
+            k_brackets: vec![KBracket { start: 0, k: 32f32 }, KBracket { start: 400, k: 0f32 }],
+            ..test_config()
+        };
+        let problems = config.validate().unwrap_err().problems;
+
+        assert_eq!(problems, vec!["k of bracket starting at 400 must be positive, got 0"]);
+    }
+
+    #[test]
+    fn validation_reports_every_bad_series_score() {
+        let config = Configuration { bo1_score: -1f32, bo5_score: 100f32, ..test_config() };
+        let problems = config.validate().unwrap_err().problems;
+
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("bo1_score"));
+        assert!(problems[1].starts_with("bo5_score"));
+    }
+
+    #[test]
+    fn parses_bo7_series() {
+        let results: Vec<MatchResult> = serde_json::from_str(r#"[{"winner": "C9", "loser": "TSM", "series": "Bo7"}]"#).unwrap();
+        assert!(matches!(results[0].series, SeriesKind::Bo7));
+
+        let config = test_config();
+        assert_eq!(get_series_win_weight_from_config(&config)(SeriesKind::Bo7), config.bo5_score);
+
+        let config = Configuration { bo7_score: Some(1.75f32), ..test_config() };
+        assert_eq!(get_series_win_weight_from_config(&config)(SeriesKind::Bo7), 1.75f32);
+    }
+
+    #[test]
+    fn ratings_never_drop_below_floor() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 300f32);
+        standings.insert("CLG".to_string(), 150f32);
+
+        let config = Configuration { rating_floor: Some(100f32), ..test_config() };
+        let results: Vec<MatchResult> = (0..20).map(|_| test_match("C9", "CLG")).collect();
+
+        let history = apply_match_results_with_history(&results, &standings, &config).unwrap();
+        assert!(history.iter().all(|standings| standings["CLG"] >= 100f32));
+        assert_eq!(history.last().unwrap()["CLG"], 100f32);
+    }
+
+    #[test]
+    fn team_k_override_only_affects_named_team() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+
+        let mut team_k_overrides = HashMap::new();
+        team_k_overrides.insert("C9".to_string(), 10f32);
+        let config = Configuration { team_k_overrides, ..test_config() };
+
+        let update = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &config).unwrap();
+        assert_eq!(update.winner_k, 10f32);
+        assert_eq!(update.loser_k, 20f32);
+        assert_eq!(update.winner_delta, 5f32);
+        assert_eq!(update.loser_delta, -10f32);
+    }
+
+    #[test]
+    fn team_k_overrides_apply_to_both_teams() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+
+        let mut team_k_overrides = HashMap::new();
+        team_k_overrides.insert("C9".to_string(), 10f32);
+        team_k_overrides.insert("TSM".to_string(), 60f32);
+        let config = Configuration { team_k_overrides, ..test_config() };
+
+        let update = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &config).unwrap();
+        assert_eq!(update.winner_delta, 5f32);
+        assert_eq!(update.loser_delta, -30f32);
+    }
+
+    #[test]
+    fn parses_toml_configuration() {
+        let path = std::env::temp_dir().join("elo_parses_toml_configuration.toml");
+        std::fs::write(&path, r#"
+bo1_score = 1
+bo3_score = 1.25
+bo5_score = 1.5
+
+[[k_brackets]]
+start = 0
+k = 32
+
+[[k_brackets]]
+start = 400
+k = 28
+"#).unwrap();
+
+        let config: Configuration = parse_type_from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.bo3_score, 1.25f32);
+        assert_eq!(config.k_brackets.len(), 2);
+        assert_eq!(config.k_brackets[1].k, 28f32);
+        assert_eq!(config.default_rating(), 1500f32);
+    }
+
+    #[test]
+    fn provisional_k_applies_until_enough_games_are_played() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+
+        let config = Configuration { provisional_games: 2, provisional_k: Some(80f32), ..test_config() };
+        let results: Vec<MatchResult> = (0..3).map(|_| test_match("C9", "TSM")).collect();
+
+        let mut updates = Vec::new();
+        apply_match_results_inspect(&results, &standings, &config, |update, _| -> Result<(), EloError> {
+            updates.push((update.winner_k, update.loser_k));
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(updates, vec![(80f32, 80f32), (80f32, 80f32), (20f32, 20f32)]);
+    }
+
+    #[test]
+    fn provisional_k_is_per_team() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("C9".to_string(), 5);
+        let config = Configuration { provisional_games: 2, provisional_k: Some(80f32), ..test_config() };
+
+        let update = get_match_update(&test_match("C9", "TSM"), &standings, &games_played, &config).unwrap();
+        assert_eq!(update.winner_k, 20f32);
+        assert_eq!(update.loser_k, 80f32);
+    }
+
+    #[test]
+    fn home_winner_gains_less_than_neutral_winner() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+
+        let config = Configuration { home_advantage: Some(100f32), ..test_config() };
+        let neutral = test_match("C9", "TSM");
+        let home_win = MatchResult { home: Some("C9".to_string()), ..test_match("C9", "TSM") };
+        let away_win = MatchResult { home: Some("TSM".to_string()), ..test_match("C9", "TSM") };
+
+        let neutral_update = get_match_update(&neutral, &standings, &GamesPlayed::new(), &config).unwrap();
+        let home_update = get_match_update(&home_win, &standings, &GamesPlayed::new(), &config).unwrap();
+        let away_update = get_match_update(&away_win, &standings, &GamesPlayed::new(), &config).unwrap();
+
+        assert_eq!(neutral_update.winner_delta, 10f32);
+        assert!(home_update.winner_delta < neutral_update.winner_delta);
+        assert!(away_update.winner_delta > neutral_update.winner_delta);
+        assert_eq!(home_update.winner_rating, 1500f32);
+        assert!((home_update.winner_delta + home_update.loser_delta).abs() < 1e-4);
+    }
+
+    #[test]
+    fn decay_moves_inactive_teams_toward_mean() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1800f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("CLG".to_string(), 1200f32);
+        standings.insert("TL".to_string(), 1500f32);
+
+        let mut active_teams = HashSet::new();
+        active_teams.insert("TL".to_string());
+        active_teams.insert("TSM".to_string());
+        let decayed = decay_standings(&standings, 0.5f32, &active_teams);
+
+        assert_eq!(decayed["C9"], 1650f32);
+        assert_eq!(decayed["CLG"], 1350f32);
+        assert_eq!(decayed["TL"], 1500f32);
+        assert_eq!(mean_rating(&decayed), mean_rating(&standings));
+    }
+
+    #[test]
+    fn decay_preserves_mean_for_uneven_pool() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 2000f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("CLG".to_string(), 1300f32);
+
+        let mut active_teams = HashSet::new();
+        active_teams.insert("CLG".to_string());
+        let decayed = decay_standings(&standings, 0.25f32, &active_teams);
+
+        assert!(decayed["C9"] < 2000f32);
+        assert!((mean_rating(&decayed).unwrap() - mean_rating(&standings).unwrap()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn movers_lists_biggest_risers_and_fallers() {
+        let mut before = Standings::new();
+        before.insert("C9".to_string(), 1500f32);
+        before.insert("TSM".to_string(), 1500f32);
+        before.insert("CLG".to_string(), 1500f32);
+        before.insert("TL".to_string(), 1500f32);
+
+        let mut after = before.clone();
+        after.insert("C9".to_string(), 1530f32);
+        after.insert("TSM".to_string(), 1510f32);
+        after.insert("CLG".to_string(), 1470f32);
+        after.insert("TL".to_string(), 1490f32);
+
+        assert_eq!(format_movers(&before, &after, 1500f32, 1), "Risers:\n  C9 +30.0\nFallers:\n  CLG -30.0\n");
+    }
+
+    #[test]
+    fn parses_custom_series() {
+        let results: Vec<MatchResult> = serde_json::from_str(r#"[{"winner": "C9", "loser": "TSM", "series": {"Custom": 9}}]"#).unwrap();
+        assert!(matches!(results[0].series, SeriesKind::Custom(9)));
+        assert!(matches!("bo9".parse(), Ok(SeriesKind::Custom(9))));
+        assert!("bo0".parse::<SeriesKind>().is_err());
+    }
+
+    #[test]
+    fn custom_series_scores_use_lookup_then_formula() {
+        let mut custom_scores = HashMap::new();
+        custom_scores.insert(9, 2f32);
+        let config = Configuration { custom_scores, ..test_config() };
+        let series_win_weight = get_series_win_weight_from_config(&config);
+
+        assert_eq!(series_win_weight(SeriesKind::Custom(9)), 2f32);
+        assert_eq!(series_win_weight(SeriesKind::Custom(3)), config.bo3_score);
+        assert_eq!(series_win_weight(SeriesKind::Custom(2)), 1.125f32);
+    }
+
+    #[test]
+    fn cross_reference_finds_mismatched_names() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("CLG".to_string(), 1500f32);
+
+        let results = vec![test_match("C9", "tsm"), test_match("tsm", "CLG")];
+
+        assert_eq!(cross_reference_teams(&results, &standings), TeamCrossReference {
+            unknown_teams: vec!["tsm".to_string()],
+            unplayed_teams: vec!["TSM".to_string()],
+        });
+        assert!(cross_reference_teams(&[test_match("C9", "TSM"), test_match("CLG", "C9")], &standings).is_clean());
+
+        let mut config = test_config();
+        config.aliases.insert("Cloud9".to_string(), "C9".to_string());
+        let aliased = vec![test_match("Cloud9", "TSM"), test_match("CLG", "TSM")];
+        assert!(cross_reference_canonical_teams(&aliased, &standings, &config).is_clean());
+        assert_eq!(cross_reference_teams(&aliased, &standings).unknown_teams, vec!["Cloud9".to_string()]);
+    }
+
+    #[test]
+    fn json_output_has_sorted_keys() {
+        let mut standings = Standings::new();
+        for team in ["TSM", "C9", "TL", "CLG", "100T", "EG"].iter() {
+            standings.insert(team.to_string(), 1500f32);
+        }
+
+        let json = to_sorted_json_string(&standings).unwrap();
+        let keys: Vec<&str> = json.lines().filter_map(|line| line.split('"').nth(1)).collect();
+
+        assert_eq!(keys, vec!["100T", "C9", "CLG", "EG", "TL", "TSM"]);
+    }
+
+    #[test]
+    fn undo_reverses_apply() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1250f32);
+        standings.insert("TSM".to_string(), 1700f32);
+
+        let config = test_config();
+        let result = MatchResult { series: SeriesKind::Bo3, ..test_match("C9", "TSM") };
+        let applied = apply_match_result(&result, &standings, &config).unwrap();
+        let undone = undo_match_result(&result, &applied, &config).unwrap();
+
+        assert!((undone["C9"] - 1250f32).abs() < 1e-3);
+        assert!((undone["TSM"] - 1700f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn reads_and_writes_yaml() {
+        let dir = std::env::temp_dir();
+        let standings_path = dir.join("elo_reads_and_writes_yaml_standings.yaml");
+        let matches_path = dir.join("elo_reads_and_writes_yaml_matches.yml");
+        let output_path = dir.join("elo_reads_and_writes_yaml_output.yaml");
+        std::fs::write(&standings_path, "C9: 1500\nTSM: 1500\n").unwrap();
+        std::fs::write(&matches_path, "- winner: C9\n  loser: TSM\n  series: Bo1\n").unwrap();
+
+        let standings = parse_standings_from_path(&standings_path).unwrap();
+        let results = parse_match_results_from_path(&matches_path).unwrap();
+        let new_standings = apply_match_results(&results, &standings, &test_config()).unwrap();
+        write_standings_to_path(&output_path, &new_standings).unwrap();
+        let written = parse_standings_from_path(&output_path).unwrap();
+
+        for path in [standings_path, matches_path, output_path].iter() {
+            std::fs::remove_file(path).unwrap();
+        }
+        assert_eq!(new_standings["C9"], 1510f32);
+        assert_eq!(new_standings["TSM"], 1490f32);
+        assert_eq!(written, new_standings);
+    }
+
+    #[test]
+    fn default_scale_matches_standard_formula() {
+        let config: Configuration = serde_json::from_str(
+            r#"{"bo1_score": 1, "bo3_score": 1, "bo5_score": 1, "k_brackets": [{"start": 0, "k": 32}]}"#,
+        ).unwrap();
+
+        assert_eq!(config.scale(), EloScale::default());
+        assert_eq!(config.expected_probabilities(1700f32, 1300f32), get_expected_probabilities(1700f32, 1300f32));
+        assert!((get_expected_probabilities(1700f32, 1300f32).0 - 10f32 / 11f32).abs() < 1e-6);
+    }
+
+    #[test]
+    fn larger_divisor_narrows_probability_spread() {
+        let default_config = test_config();
+        let wide_config = Configuration { elo_divisor: 800f32, ..test_config() };
+
+        let (default_favourite, _) = default_config.expected_probabilities(1700f32, 1300f32);
+        let (wide_favourite, wide_underdog) = wide_config.expected_probabilities(1700f32, 1300f32);
+
+        assert!(wide_favourite < default_favourite);
+        assert!(wide_favourite > 0.5f32);
+        assert!((wide_favourite + wide_underdog - 1f32).abs() < 1e-6);
+    }
+
+    #[test]
+    fn errors_can_be_matched_on() {
+        let missing = parse_standings_from_path(Path::new("elo_errors_can_be_matched_on_missing.json"));
+        assert!(matches!(missing, Err(EloError::FileNotFound(_))));
+
+        let path = std::env::temp_dir().join("elo_errors_can_be_matched_on_invalid.json");
+        std::fs::write(&path, "{ not json").unwrap();
+        let invalid = parse_standings_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(invalid, Err(EloError::ParseError { .. })));
+
+        let standings = Standings::new();
+        assert!(matches!(get_probabilities_from_standings(&standings, "C9", "TSM"), Err(EloError::MissingTeam(team)) if team == "C9"));
+
+        let config = Configuration { k_brackets: vec![], default_rating: Some(1500f32), ..test_config() };
+        let error = apply_match_results(&[test_match("C9", "TSM")], &standings, &config).unwrap_err();
+        assert!(matches!(error, EloError::InMatch { index: 0, ref source, .. } if matches!(**source, EloError::NoKBracket(_))));
+    }
+
+    #[test]
+    fn team_list_creates_standings_at_default_rating() {
+        let teams = parse_team_list("C9\nTSM\n\n  CLG  \nTL\n").unwrap();
+        assert_eq!(teams, vec!["C9", "TSM", "CLG", "TL"]);
+        assert_eq!(parse_team_list(r#"["C9", "TSM", "CLG", "TL"]"#).unwrap(), teams);
+
+        let standings = standings_from_team_list(&teams, 1200f32);
+        assert_eq!(standings.len(), 4);
+        assert!(standings.values().all(|rating| *rating == 1200f32));
+    }
+
+    #[test]
+    fn rating_deviation_shrinks_with_games() {
+        assert_eq!(rating_deviation(0), INITIAL_RATING_DEVIATION);
+        assert_eq!(rating_deviation(3), INITIAL_RATING_DEVIATION / 2f32);
+        assert!((1..50).all(|games| rating_deviation(games) < rating_deviation(games - 1)));
+        assert_eq!(rating_deviation(10_000), MIN_RATING_DEVIATION);
+    }
+
+    #[test]
+    fn uncertainty_uses_games_played() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1600f32);
+        standings.insert("TSM".to_string(), 1400f32);
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("C9".to_string(), 8);
+
+        let teams = standings_with_uncertainty(&standings, &games_played, &DisplayNames::new(), &Divisions::new());
+        assert_eq!(teams["C9"], RatedTeam { rating: 1600f32, games: 8, rd: 350f32 / 3f32, display_name: None, division: None });
+        assert_eq!(teams["TSM"].rd, INITIAL_RATING_DEVIATION);
+    }
+
+    #[test]
+    fn k_selection_picks_bracket_rating() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 2600f32);
+        standings.insert("TSM".to_string(), 1000f32);
+        let k_for = |k_selection, winner: &str, loser: &str| {
+            let config = Configuration { k_selection, ..test_config() };
+            get_match_update(&test_match(winner, loser), &standings, &GamesPlayed::new(), &config).unwrap().winner_k
+        };
+
+        // Average of 1800 is in the 1200 bracket.
+        assert_eq!(k_for(KSelection::Average, "C9", "TSM"), 20f32);
+        assert_eq!(k_for(KSelection::Winner, "C9", "TSM"), 10f32);
+        assert_eq!(k_for(KSelection::Winner, "TSM", "C9"), 40f32);
+        assert_eq!(k_for(KSelection::Loser, "C9", "TSM"), 40f32);
+        assert_eq!(k_for(KSelection::Loser, "TSM", "C9"), 10f32);
+        // A gap of 1600 is in the 1200 bracket whichever team wins.
+        assert_eq!(k_for(KSelection::Difference, "C9", "TSM"), 20f32);
+        assert_eq!(k_for(KSelection::Difference, "TSM", "C9"), 20f32);
+    }
+
+    #[test]
+    fn k_selection_parses_lowercase_names() {
+        let config: Configuration = serde_json::from_value(serde_json::json!({
+            "bo1_score": 1, "bo3_score": 1, "bo5_score": 1,
+            "k_brackets": [{ "start": 0, "k": 32 }],
+            "k_selection": "difference"
+        })).unwrap();
+        assert_eq!(config.k_selection, KSelection::Difference);
+    }
+
+    #[test]
+    fn importance_scales_rating_changes() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let config = test_config();
+
+        let regular = MatchResult { importance: Some(1f32), ..test_match("C9", "TSM") };
+        let playoff = MatchResult { importance: Some(2f32), ..test_match("C9", "TSM") };
+        let regular_update = get_match_update(&regular, &standings, &GamesPlayed::new(), &config).unwrap();
+        let playoff_update = get_match_update(&playoff, &standings, &GamesPlayed::new(), &config).unwrap();
+        let unweighted_update = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &config).unwrap();
+
+        assert_eq!(regular_update.winner_delta, unweighted_update.winner_delta);
+        assert_eq!(playoff_update.winner_k, 2f32 * regular_update.winner_k);
+        assert!((playoff_update.winner_delta - 2f32 * regular_update.winner_delta).abs() < 1e-4);
+        assert!((playoff_update.loser_delta - 2f32 * regular_update.loser_delta).abs() < 1e-4);
+    }
+
+    #[test]
+    fn summary_counts_matches_and_new_teams() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("CLG".to_string(), 1500f32);
+        let results = vec![test_match("C9", "TSM"), test_match("TL", "C9"), test_match("C9", "BYE")];
+        let config = Configuration { ignored_teams: ["BYE".to_string()].iter().cloned().collect(), ..test_config() };
+        let new_standings = apply_match_results(&results, &standings, &config).unwrap();
+
+        let summary = summarize_run(&standings, &new_standings, &results, &config);
+        assert_eq!(summary.matches_applied, 2);
+        assert_eq!(summary.teams_affected, 3);
+        assert_eq!(summary.new_teams, 1);
+        assert_eq!(summary.highest.as_ref().map(|(team, _)| team.as_str()), Some("TL"));
+        assert_eq!(summary.lowest.as_ref().map(|(team, _)| team.as_str()), Some("TSM"));
+        assert_eq!(summary.mean, mean_rating(&new_standings));
+        assert!(summary.to_string().starts_with("Matches applied: 2\nTeams affected: 3\nNew teams: 1\n"));
+    }
+
+    #[test]
+    fn sort_by_date_orders_matches_chronologically() {
+        let dated = |winner: &str, loser: &str, date: Option<&str>| MatchResult { date: date.map(String::from), ..test_match(winner, loser) };
+        let mut results = vec![
+            dated("C9", "TSM", None),
+            dated("TSM", "C9", Some("2021-06-19")),
+            dated("CLG", "TL", None),
+            dated("C9", "TSM", Some("2021-06-12")),
+            dated("TL", "CLG", Some("2021-06-12")),
+        ];
+        sort_by_date(&mut results);
+
+        let order: Vec<(&str, Option<&str>)> = results.iter().map(|result| (result.winner.as_str(), result.date.as_deref())).collect();
+        assert_eq!(order, vec![
+            ("C9", Some("2021-06-12")),
+            ("TL", Some("2021-06-12")),
+            ("TSM", Some("2021-06-19")),
+            ("C9", None),
+            ("CLG", None),
+        ]);
+    }
+
+    #[test]
+    fn sorting_by_date_changes_standings() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let config = test_config();
+        let mut results = vec![
+            MatchResult { date: Some("2021-06-19".to_string()), ..test_match("TSM", "C9") },
+            MatchResult { date: Some("2021-06-12".to_string()), ..test_match("C9", "TSM") },
+            MatchResult { date: Some("2021-06-13".to_string()), ..test_match("C9", "TSM") },
+        ];
+
+        let unsorted_standings = apply_match_results(&results, &standings, &config).unwrap();
+        sort_by_date(&mut results);
+        let sorted_standings = apply_match_results(&results, &standings, &config).unwrap();
+
+        // Sorted, C9 wins twice and then loses to TSM as the favourite, which costs it more than
+        // the loss at even ratings it starts with when unsorted.
+        let expected = apply_match_results(&[test_match("C9", "TSM"), test_match("C9", "TSM"), test_match("TSM", "C9")], &standings, &config).unwrap();
+        assert_eq!(sorted_standings, expected);
+        assert!(sorted_standings["C9"] < unsorted_standings["C9"]);
+    }
+
+    #[test]
+    fn self_match_is_an_error() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let results = vec![test_match("C9", "TSM"), test_match("C9", "C9")];
+
+        let error = apply_match_results(&results, &standings, &test_config()).unwrap_err();
+        assert!(matches!(error, EloError::InMatch { index: 1, ref source, .. } if matches!(**source, EloError::SelfMatch(ref team) if team == "C9")));
+        assert_eq!(error.to_string(), "match 1 (C9 beat C9 in a Bo1): team 'C9' cannot play a match against itself");
+    }
+
+    #[test]
+    fn update_ratings_matches_apply_match_result() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1300f32);
+        standings.insert("TSM".to_string(), 1200f32);
+        let config = Configuration { rating_floor: Some(1195f32), ..test_config() };
+        let result = MatchResult { series: SeriesKind::Bo3, ..test_match("C9", "TSM") };
+
+        let new_standings = apply_match_result(&result, &standings, &config).unwrap();
+        let new_ratings = update_ratings(1300f32, 1200f32, SeriesKind::Bo3, &config).unwrap();
+        assert_eq!(new_ratings, (new_standings["C9"], new_standings["TSM"]));
+        assert_eq!(new_ratings.1, 1195f32);
+    }
+
+    #[test]
+    fn upset_factor_moves_upsets_more() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1600f32);
+        standings.insert("TSM".to_string(), 1400f32);
+        let plain_config = test_config();
+        let upset_config = Configuration { upset_factor: Some(1f32), ..test_config() };
+        let k_for = |winner: &str, loser: &str, config: &Configuration| {
+            get_match_update(&test_match(winner, loser), &standings, &GamesPlayed::new(), config).unwrap().winner_k
+        };
+
+        let favourite_expected = get_expected_probabilities(1600f32, 1400f32).0;
+        assert_eq!(k_for("C9", "TSM", &plain_config), 20f32);
+        assert_eq!(k_for("TSM", "C9", &plain_config), 20f32);
+        assert!((k_for("C9", "TSM", &upset_config) - 20f32 * (2f32 - favourite_expected)).abs() < 1e-4);
+        assert!((k_for("TSM", "C9", &upset_config) - 20f32 * (1f32 + favourite_expected)).abs() < 1e-4);
+        assert!(k_for("TSM", "C9", &upset_config) > k_for("C9", "TSM", &upset_config));
+    }
+
+    #[test]
+    fn negative_upset_factor_is_invalid() {
+        let config = Configuration { upset_factor: Some(-1f32), ..test_config() };
+        assert_eq!(config.validate().unwrap_err().problems, vec!["upset_factor must not be negative, got -1".to_string()]);
+    }
+
+    #[test]
+    fn free_for_all_combines_pairwise_updates() {
+        let mut standings = Standings::new();
+        for team in ["C9", "TSM", "CLG", "TL"].iter() {
+            standings.insert(team.to_string(), 1500f32);
+        }
+        let result = FreeForAll { placements: vec!["TL".to_string(), "C9".to_string(), "TSM".to_string(), "CLG".to_string()], ..Default::default() };
+
+        let new_standings = apply_free_for_all(&result, &standings, &test_config()).unwrap();
+        // At even ratings every pair moves 20 * 0.5 / 3 points.
+        let pair_delta = 10f32 / 3f32;
+        assert!((new_standings["TL"] - (1500f32 + 3f32 * pair_delta)).abs() < 1e-3);
+        assert!((new_standings["C9"] - (1500f32 + pair_delta)).abs() < 1e-3);
+        assert!((new_standings["TSM"] - (1500f32 - pair_delta)).abs() < 1e-3);
+        assert!((new_standings["CLG"] - (1500f32 - 3f32 * pair_delta)).abs() < 1e-3);
+        assert!((new_standings.values().sum::<f32>() - 6000f32).abs() < 1e-2);
+    }
+
+    #[test]
+    fn free_for_all_rejects_repeated_teams() {
+        let result = FreeForAll { placements: vec!["TL".to_string(), "C9".to_string(), "TL".to_string()], ..Default::default() };
+        let error = apply_free_for_all(&result, &Standings::new(), &test_config()).unwrap_err();
+        assert!(matches!(error, EloError::SelfMatch(ref team) if team == "TL"));
+
+        let mut config = test_config();
+        config.aliases.insert("Cloud9".to_string(), "C9".to_string());
+        let result = FreeForAll { placements: vec!["Cloud9".to_string(), "TL".to_string(), "C9".to_string()], ..Default::default() };
+        let error = apply_free_for_all(&result, &Standings::new(), &config).unwrap_err();
+        assert!(matches!(error, EloError::SelfMatch(ref team) if team == "C9"));
+    }
+
+    #[test]
+    fn free_for_all_limits_the_summed_change() {
+        let mut standings = Standings::new();
+        for team in ["C9", "TSM", "CLG", "TL", "FLY"].iter() {
+            standings.insert(team.to_string(), 1500f32);
+        }
+        let placements = |teams: &[&str]| teams.iter().map(|team| team.to_string()).collect::<Vec<String>>();
+        let config = Configuration { max_delta_per_match: Some(5f32), rating_floor: Some(1495f32), ..test_config() };
+
+        // Uncapped, first of five gains 4 * 10 / 4 = 10 and last loses 10, each pair only moving
+        // 2.5 so a per pair cap of 5 would never bind.
+        let result = FreeForAll { placements: placements(&["TL", "C9", "TSM", "CLG", "FLY"]), ..Default::default() };
+        let new_standings = apply_free_for_all(&result, &standings, &config).unwrap();
+        assert!((new_standings["TL"] - 1505f32).abs() < 1e-3);
+        assert!((new_standings["C9"] - 1505f32).abs() < 1e-3);
+        assert!((new_standings["TSM"] - 1500f32).abs() < 1e-3);
+        assert!((new_standings["CLG"] - 1495f32).abs() < 1e-3);
+        assert!((new_standings["FLY"] - 1495f32).abs() < 1e-3);
+
+        let floored = Configuration { rating_floor: Some(1497f32), ..test_config() };
+        let new_standings = apply_free_for_all(&result, &standings, &floored).unwrap();
+        assert_eq!(new_standings["FLY"], 1497f32);
+        assert_eq!(new_standings["CLG"], 1497f32);
+        assert!((new_standings["TL"] - 1510f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn free_for_all_skips_unrated_results_and_ignored_teams() {
+        let mut standings = Standings::new();
+        for team in ["C9", "TSM", "CLG"].iter() {
+            standings.insert(team.to_string(), 1500f32);
+        }
+        let placements = |teams: &[&str]| teams.iter().map(|team| team.to_string()).collect::<Vec<String>>();
+
+        let unrated = FreeForAll { placements: placements(&["C9", "TSM", "CLG"]), rated: false };
+        assert_eq!(apply_free_for_all(&unrated, &standings, &test_config()).unwrap(), standings);
+
+        let mut config = test_config();
+        config.ignored_teams.insert("BYE".to_string());
+        let with_bye = FreeForAll { placements: placements(&["C9", "BYE", "TSM", "CLG"]), ..Default::default() };
+        let without_bye = FreeForAll { placements: placements(&["C9", "TSM", "CLG"]), ..Default::default() };
+        let new_standings = apply_free_for_all(&with_bye, &standings, &config).unwrap();
+        assert!(!new_standings.contains_key("BYE"));
+        assert_eq!(new_standings, apply_free_for_all(&without_bye, &standings, &config).unwrap());
+    }
+
+    #[test]
+    fn ceiling_compresses_gains_above_it() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 2500f32);
+        standings.insert("TSM".to_string(), 2450f32);
+        let plain_config = test_config();
+        let compressed_config = Configuration { rating_ceiling: Some(2480f32), ceiling_compression: Some(0.5f32), ..test_config() };
+
+        let plain = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &plain_config).unwrap();
+        let compressed = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &compressed_config).unwrap();
+        assert!(compressed.winner_delta > 0f32);
+        assert!((compressed.winner_delta - plain.winner_delta / 2f32).abs() < 1e-3);
+        assert_eq!(compressed.loser_delta, plain.loser_delta);
+
+        // The team below the ceiling gains in full and the one above it loses in full.
+        let plain_upset = get_match_update(&test_match("TSM", "C9"), &standings, &GamesPlayed::new(), &plain_config).unwrap();
+        let compressed_upset = get_match_update(&test_match("TSM", "C9"), &standings, &GamesPlayed::new(), &compressed_config).unwrap();
+        assert_eq!(compressed_upset.winner_delta, plain_upset.winner_delta);
+        assert_eq!(compressed_upset.loser_delta, plain_upset.loser_delta);
+    }
+
+    #[test]
+    fn bracket_basis_is_an_alias_for_k_selection() {
+        let config_with_basis = |basis: &str| -> Configuration {
+            serde_json::from_value(serde_json::json!({
+                "bo1_score": 1, "bo3_score": 1.25, "bo5_score": 1.5,
+                "k_brackets": [{ "start": 0, "k": 40 }, { "start": 1200, "k": 20 }, { "start": 2400, "k": 10 }],
+                "bracket_basis": basis
+            })).unwrap()
+        };
+        let combined = config_with_basis("combined");
+        let difference = config_with_basis("difference");
+        assert_eq!(combined.k_selection, KSelection::Average);
+        assert_eq!(difference.k_selection, KSelection::Difference);
+
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1600f32);
+        standings.insert("TSM".to_string(), 1400f32);
+        let k_for = |config: &Configuration| get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), config).unwrap().winner_k;
+        assert_eq!(k_for(&combined), 20f32);
+        assert_eq!(k_for(&difference), 40f32);
+    }
+
+    #[test]
+    fn days_since_epoch_parses_iso_dates() {
+        assert_eq!(days_since_epoch("1970-01-01"), Some(0));
+        assert_eq!(days_since_epoch("2000-03-01"), Some(11017));
+        assert_eq!(days_since_epoch("2021-06-12T18:00:00Z"), Some(18790));
+        assert_eq!(days_since_epoch("2021-13-01"), None);
+        assert_eq!(days_since_epoch("June 12"), None);
+    }
+
+    #[test]
+    fn recency_weighting_discounts_older_matches() {
+        let mut results = vec![test_match("C9", "TSM"), test_match("TSM", "C9"), MatchResult { importance: Some(2f32), ..test_match("TSM", "C9") }];
+        weight_by_recency(&mut results, 1f32);
+        let weights: Vec<Option<f32>> = results.iter().map(|result| result.importance).collect();
+        assert_eq!(weights, vec![Some(0.25f32), Some(0.5f32), Some(2f32)]);
+
+        let mut dated = vec![
+            MatchResult { date: Some("2021-06-01".to_string()), ..test_match("C9", "TSM") },
+            MatchResult { date: Some("2021-06-15".to_string()), ..test_match("TSM", "C9") },
+        ];
+        weight_by_recency(&mut dated, 14f32);
+        assert_eq!(dated[0].importance, Some(0.5f32));
+        assert_eq!(dated[1].importance, Some(1f32));
+
+        let unrated = |result: MatchResult| MatchResult { rated: false, ..result };
+        let mut with_unrated = vec![
+            test_match("C9", "TSM"),
+            unrated(test_match("TSM", "C9")),
+            test_match("TSM", "C9"),
+            unrated(MatchResult { date: Some("2030-01-01".to_string()), ..test_match("C9", "TSM") }),
+        ];
+        weight_by_recency(&mut with_unrated, 1f32);
+        let weights: Vec<Option<f32>> = with_unrated.iter().map(|result| result.importance).collect();
+        assert_eq!(weights, vec![Some(0.5f32), None, Some(1f32), None]);
+
+        // An old win counts for less, so weighting leaves the winner of it lower.
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let mut season = vec![test_match("C9", "TSM"), test_match("C9", "TSM"), test_match("TSM", "C9")];
+        let unweighted = apply_match_results(&season, &standings, &test_config()).unwrap();
+        weight_by_recency(&mut season, 1f32);
+        let weighted = apply_match_results(&season, &standings, &test_config()).unwrap();
+        assert!(weighted["C9"] < unweighted["C9"]);
+    }
+
+    #[test]
+    fn apply_match_results_matches_applying_one_at_a_time() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1320f32);
+        let results = vec![test_match("C9", "TSM"), test_match("TSM", "C9"), test_match("CLG", "C9"), test_match("TSM", "CLG")];
+        let config = test_config();
+
+        let mut expected = standings.clone();
+        for result in results.iter() {
+            expected = apply_match_result(result, &expected, &config).unwrap();
+        }
+        assert_eq!(apply_match_results(&results, &standings, &config).unwrap(), expected);
+    }
+
+    #[test]
+    fn records_seed_ratings_by_win_rate() {
+        let mut records = HashMap::new();
+        records.insert("C9".to_string(), (10, 0));
+        records.insert("TSM".to_string(), (0, 8));
+        records.insert("CLG".to_string(), (6, 2));
+        records.insert("TL".to_string(), (0, 0));
+
+        let standings = standings_from_records(&records, 1500f32, 400f32);
+        assert_eq!(standings["C9"], 1700f32);
+        assert_eq!(standings["TSM"], 1300f32);
+        assert_eq!(standings["CLG"], 1600f32);
+        assert_eq!(standings["TL"], 1500f32);
+        assert!(standings["C9"] > standings["TSM"]);
+    }
+
+    #[test]
+    fn probability_matrix_is_symmetric() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1600f32);
+        standings.insert("TSM".to_string(), 1450f32);
+        standings.insert("CLG".to_string(), 1200f32);
+
+        let matrix = probability_matrix(&standings, EloScale::default());
+        assert_eq!(matrix.len(), 3);
+        for team_a in standings.keys() {
+            assert_eq!(matrix[team_a].len(), 3);
+            assert_eq!(matrix[team_a][team_a], 0.5f32);
+            for team_b in standings.keys() {
+                assert!((matrix[team_a][team_b] + matrix[team_b][team_a] - 1f32).abs() < 1e-6);
+            }
+        }
+        assert_eq!(matrix["C9"]["CLG"], get_expected_probabilities(1600f32, 1200f32).0);
+    }
+
+    #[test]
+    fn round_standings_rounds_to_places() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1523.4782f32);
+        standings.insert("TSM".to_string(), 1476.5218f32);
+
+        let whole = round_standings(&standings, 0);
+        assert_eq!(whole["C9"], 1523f32);
+        assert_eq!(whole["TSM"], 1477f32);
+        assert_eq!(round_standings(&standings, 2)["C9"], 1523.48f32);
+        assert_eq!(standings["C9"], 1523.4782f32);
+    }
+
+    #[test]
+    fn standings_parse_plain_and_extended_entries() {
+        let path = std::env::temp_dir().join("elo_standings_parse_plain_and_extended_entries.json");
+        std::fs::write(&path, r#"{"C9": 1500, "TSM": {"rating": 1450.5, "games": 12, "rd": 97.1}}"#).unwrap();
+
+        let (standings, games_played) = parse_standings_with_games_from_path(&path).unwrap();
+        assert_eq!(standings["C9"], 1500f32);
+        assert_eq!(standings["TSM"], 1450.5f32);
+        assert_eq!(games_played.get("C9"), None);
+        assert_eq!(games_played["TSM"], 12);
+        assert_eq!(parse_standings_from_path(&path).unwrap(), standings);
+    }
+
+    #[test]
+    fn rated_teams_round_trip_through_standings() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1523.5f32);
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("C9".to_string(), 7);
+        let path = std::env::temp_dir().join("elo_rated_teams_round_trip_through_standings.json");
+
+        write_type_to_path(&path, &standings_with_uncertainty(&standings, &games_played, &DisplayNames::new(), &Divisions::new())).unwrap();
+        assert_eq!(parse_standings_with_games_from_path(&path).unwrap(), (standings, games_played));
+    }
+
+    #[test]
+    fn unrated_matches_leave_ratings_unchanged() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1400f32);
+        let exhibition = MatchResult { rated: false, ..test_match("C9", "TSM") };
+        let config = test_config();
+
+        assert_eq!(apply_match_result(&exhibition, &standings, &config).unwrap(), standings);
+        assert_eq!(undo_match_result(&exhibition, &standings, &config).unwrap(), standings);
+
+        let results = vec![test_match("C9", "TSM"), MatchResult { rated: false, ..test_match("TSM", "TL") }];
+        let new_standings = apply_match_results(&results, &standings, &config).unwrap();
+        assert_eq!(new_standings, apply_match_results(&results[..1], &standings, &config).unwrap());
+        assert!(!new_standings.contains_key("TL"));
+        assert_eq!(summarize_run(&standings, &new_standings, &results, &config).matches_applied, 1);
+
+        let parsed: MatchResult = serde_json::from_str(r#"{"winner": "C9", "loser": "TSM", "series": "Bo1"}"#).unwrap();
+        assert!(parsed.rated);
+    }
+
+    #[test]
+    fn empty_matches_leave_standings_unchanged() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1400f32);
+
+        assert_eq!(apply_match_results(&[], &standings, &test_config()).unwrap(), standings);
+        assert_eq!(apply_match_results(&[], &Standings::new(), &test_config()).unwrap(), Standings::new());
+    }
+
+    #[test]
+    fn empty_standings_need_a_default_rating() {
+        let results = vec![test_match("C9", "TSM")];
+
+        let error = apply_match_results(&results, &Standings::new(), &test_config()).unwrap_err();
+        assert!(matches!(error, EloError::NoTeams));
+        assert!(error.to_string().starts_with("no teams defined"));
+
+        let config = Configuration { default_rating: Some(1500f32), ..test_config() };
+        let new_standings = apply_match_results(&results, &Standings::new(), &config).unwrap();
+        assert!(new_standings["C9"] > 1500f32);
+    }
+
+    #[test]
+    fn run_report_records_every_team() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1450f32);
+        standings.insert("CLG".to_string(), 1400f32);
+        let results = vec![test_match("C9", "TSM"), test_match("TL", "C9")];
+        let config = test_config();
+        let new_standings = apply_match_results(&results, &standings, &config).unwrap();
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("CLG".to_string(), 4);
+        let mut wins = Wins::new();
+        wins.insert("TL".to_string(), 1);
+
+        let report = build_run_report(&config, &standings, &new_standings, &results, &games_played, &wins, vec!["a warning".to_string()]);
+        assert_eq!(report.matches_applied, 2);
+        assert_eq!(report.new_teams, vec!["TL".to_string()]);
+        assert_eq!(report.teams["CLG"], TeamReport { before: Some(1400f32), after: 1400f32, delta: 0f32, games: 4, wins: 0, display_name: None });
+        assert_eq!(report.teams["TL"].wins, 1);
+        assert_eq!(report.teams["TL"].before, None);
+        assert_eq!(report.teams["TL"].delta, new_standings["TL"] - 1500f32);
+        assert_eq!(report.warnings, vec!["a warning".to_string()]);
+    }
+
+    #[test]
+    fn max_delta_caps_rating_changes() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 2000f32);
+        standings.insert("TSM".to_string(), 1000f32);
+        let config = Configuration { max_delta_per_match: Some(15f32), ..test_config() };
+
+        let upset = get_match_update(&test_match("TSM", "C9"), &standings, &GamesPlayed::new(), &config).unwrap();
+        assert_eq!(upset.winner_delta, 15f32);
+        assert_eq!(upset.loser_delta, -15f32);
+        assert_eq!(update_ratings(1000f32, 2000f32, SeriesKind::Bo1, &config).unwrap(), (1015f32, 1985f32));
+
+        // Changes below the cap are left alone.
+        let expected = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &config).unwrap();
+        assert!(expected.winner_delta > 0f32 && expected.winner_delta < 1f32);
+
+        let invalid = Configuration { max_delta_per_match: Some(0f32), ..test_config() };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn aliases_resolve_to_canonical_team() {
+        let mut aliases = HashMap::new();
+        aliases.insert("Cloud 9".to_string(), "C9".to_string());
+        aliases.insert("Cloud9".to_string(), "C9".to_string());
+        let config = Configuration { aliases, default_rating: Some(1500f32), ..test_config() };
+        let results = [test_match("Cloud 9", "TSM"), test_match("Cloud9", "TSM")];
+
+        let standings = apply_match_results(&results, &Standings::new(), &config).unwrap();
+        let unaliased = apply_match_results(&[test_match("C9", "TSM"), test_match("C9", "TSM")], &Standings::new(), &config).unwrap();
+
+        assert_eq!(standings.len(), 2);
+        assert_eq!(standings["C9"], unaliased["C9"]);
+        assert_eq!(summarize_run(&Standings::new(), &standings, &results, &config).teams_affected, 2);
+        assert!(matches!(apply_match_result(&test_match("Cloud9", "C9"), &standings, &config), Err(EloError::SelfMatch(_))));
+    }
+
+    #[test]
+    fn compare_reports_teams_outside_tolerance() {
+        let mut computed = Standings::new();
+        computed.insert("C9".to_string(), 1500f32);
+        computed.insert("TSM".to_string(), 1400.4f32);
+        computed.insert("TL".to_string(), 1300f32);
+        let mut reference = Standings::new();
+        reference.insert("C9".to_string(), 1502f32);
+        reference.insert("TSM".to_string(), 1400f32);
+        reference.insert("CLG".to_string(), 1200f32);
+
+        let mismatches = compare_standings(&computed, &reference, 0.5f32);
+        let teams: Vec<&str> = mismatches.iter().map(|mismatch| mismatch.team.as_str()).collect();
+
+        assert_eq!(teams, ["C9", "CLG", "TL"]);
+        assert_eq!(mismatches[0].to_string(), "C9: computed 1500.0, reference 1502.0 (-2.0)");
+        assert!(compare_standings(&computed, &computed, 0f32).is_empty());
+    }
+
+    #[test]
+    fn gzipped_standings_round_trip() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1523.5f32);
+        standings.insert("TSM".to_string(), 1476.5f32);
+        let path = std::env::temp_dir().join("elo_gzipped_standings_round_trip.json.gz");
+
+        write_standings_to_path(&path, &standings).unwrap();
+        let contents = std::fs::read(&path).unwrap();
+
+        assert_eq!(contents[..2], [0x1f, 0x8b]);
+        assert_eq!(parse_standings_from_path(&path).unwrap(), standings);
+        assert!(has_extension(&path, "json") && !has_extension(&path, "gz"));
+    }
+
+    #[test]
+    fn leaderboard_breaks_ties_by_games_played() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("TL".to_string(), 1500f32);
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("TSM".to_string(), 12);
+        games_played.insert("TL".to_string(), 3);
+        games_played.insert("C9".to_string(), 3);
+
+        let leaderboard = format_leaderboard_with_games(&standings, &games_played);
+        let lines: Vec<&str> = leaderboard.lines().collect();
+
+        assert_eq!(lines[1], "   1  TSM     1500.0");
+        assert_eq!(lines[2], "   2  C9      1500.0");
+        assert_eq!(lines[3], "   3  TL      1500.0");
+    }
+
+    #[test]
+    fn date_filter_limits_applied_matches() {
+        let dated = |winner, loser, date: &str| MatchResult { date: Some(date.to_string()), ..test_match(winner, loser) };
+        let results = vec![
+            dated("C9", "TSM", "2021-05-30"),
+            dated("TSM", "C9", "2021-06-01"),
+            dated("TSM", "C9", "2021-06-07T18:00:00Z"),
+            test_match("C9", "TSM"),
+        ];
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let config = test_config();
+
+        let mut filtered = results.clone();
+        filter_by_date(&mut filtered, Some("2021-06-01"), Some("2021-06-07"), false).unwrap();
+        let dates: Vec<&str> = filtered.iter().map(|result| result.date.as_deref().unwrap()).collect();
+        assert_eq!(dates, ["2021-06-01", "2021-06-07T18:00:00Z"]);
+
+        let filtered_standings = apply_match_results(&filtered, &standings, &config).unwrap();
+        assert_ne!(filtered_standings, apply_match_results(&results, &standings, &config).unwrap());
+        assert!(filtered_standings["TSM"] > filtered_standings["C9"]);
+
+        let mut with_undated = results.clone();
+        filter_by_date(&mut with_undated, Some("2021-06-01"), None, true).unwrap();
+        assert_eq!(with_undated.len(), 3);
+
+        let mut unfiltered = results.clone();
+        filter_by_date(&mut unfiltered, None, None, false).unwrap();
+        assert_eq!(unfiltered.len(), 4);
+        assert!(filter_by_date(&mut unfiltered, Some("June"), None, false).is_err());
+    }
+
+    #[test]
+    fn team_report_follows_one_team() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("TL".to_string(), 1500f32);
+        let results = vec![test_match("C9", "TSM"), test_match("TL", "TSM"), MatchResult { draw: true, ..test_match("TL", "C9") }];
+        let config = Configuration { k_brackets: vec![KBracket { start: 0, k: 32f32 }], ..test_config() };
+
+        let mut matches = Vec::new();
+        let new_standings = apply_match_results_inspect(&results, &standings, &config, |update, _| -> Result<(), EloError> {
+            matches.extend(update.for_team("C9"));
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0], TeamMatch { opponent: "TSM".to_string(), outcome: Outcome::Win, rating_before: 1500f32, rating_after: 1516f32 });
+        assert_eq!(matches[1].outcome, Outcome::Draw);
+
+        let report = format_team_report("C9", &matches, &new_standings, &GamesPlayed::new(), &DisplayNames::new()).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "C9");
+        assert_eq!(lines[1], "   1  vs TSM  win    1500.0 ->  1516.0  (+16.0, +16.0 total)");
+        assert!(lines[2].starts_with("   2  vs TL   draw   1516.0 -> "));
+        assert_eq!(lines[3], format!("Final rating: {:.1}, rank 1 of 3", new_standings["C9"]));
+        assert!(matches!(format_team_report("CLG", &matches, &new_standings, &GamesPlayed::new(), &DisplayNames::new()), Err(EloError::MissingTeam(_))));
+    }
+
+    #[test]
+    fn calibration_scores_predictions() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1100f32);
+        let config = test_config();
+        let mut calibration = Calibration::default();
+        assert_eq!(calibration.brier_score(), None);
+
+        // C9 is expected to win 10 in 11 at a 400 point gap.
+        calibration.record(&get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &config).unwrap());
+        calibration.record(&get_match_update(&test_match("TSM", "C9"), &standings, &GamesPlayed::new(), &config).unwrap());
+
+        assert_eq!(calibration.matches, 2);
+        assert!((calibration.brier_score().unwrap() - 101f32 / 242f32).abs() < 1e-5);
+        let log_loss = -((10f32 / 11f32).ln() + (1f32 / 11f32).ln()) / 2f32;
+        assert!((calibration.log_loss().unwrap() - log_loss).abs() < 1e-5);
+
+        let mut even = Calibration::default();
+        even.record(&get_match_update(&MatchResult { draw: true, ..test_match("C9", "TSM") }, &standings, &GamesPlayed::new(), &config).unwrap());
+        assert!((even.brier_score().unwrap() - 81f32 / 484f32).abs() < 1e-5);
+    }
+
+    #[test]
+    fn leaderboard_shows_display_names_while_matches_use_ids() {
+        let path = std::env::temp_dir().join("elo_leaderboard_shows_display_names_while_matches_use_ids.json");
+        std::fs::write(&path, r#"{"t17": {"rating": 1500, "display_name": "Team Liquid"}, "t3": 1500}"#).unwrap();
+
+        let file = parse_standings_file_from_path(&path).unwrap();
+        assert_eq!(file.display_names["t17"], "Team Liquid");
+        assert_eq!(file.games_played["t17"], 0);
+        let standings = apply_match_result(&test_match("t17", "t3"), &file.standings, &test_config()).unwrap();
+        assert_eq!(standings.len(), 2);
+
+        let leaderboard = format_leaderboard_with_names(&standings, &file.games_played, &file.display_names, 0);
+        let lines: Vec<&str> = leaderboard.lines().collect();
+        assert_eq!(lines[1], "   1  Team Liquid    1510.0");
+        assert_eq!(lines[2], "   2  t3             1490.0");
+
+        let teams = standings_with_uncertainty(&standings, &file.games_played, &file.display_names, &file.divisions);
+        assert_eq!(teams["t17"].display_name.as_deref(), Some("Team Liquid"));
+        assert_eq!(teams["t3"].display_name, None);
+    }
+
+    #[test]
+    fn leaderboard_lists_provisional_teams_separately() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1550f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("TL".to_string(), 1600f32);
+        standings.insert("CLG".to_string(), 1450f32);
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("C9".to_string(), 10);
+        games_played.insert("TSM".to_string(), 3);
+        games_played.insert("TL".to_string(), 2);
+
+        let leaderboard = format_leaderboard_with_names(&standings, &games_played, &DisplayNames::new(), 3);
+        let lines: Vec<&str> = leaderboard.lines().collect();
+
+        assert_eq!(lines, [
+            "Rank  Team    Rating",
+            "   1  C9      1550.0",
+            "   2  TSM     1500.0",
+            "Unranked, fewer than 3 games:",
+            "   -  TL      1600.0",
+            "   -  CLG     1450.0",
+        ]);
+        assert!(!format_leaderboard_with_names(&standings, &games_played, &DisplayNames::new(), 0).contains("Unranked"));
+    }
+
+    #[test]
+    fn non_finite_ratings_are_rejected() {
+        let path = std::env::temp_dir().join("elo_non_finite_ratings_are_rejected.yaml");
+        std::fs::write(&path, "C9: .nan\nTSM: 1500\n").unwrap();
+        let error = parse_standings_from_path(&path).unwrap_err();
+        assert!(matches!(error, EloError::ParseError { .. }));
+        assert!(error.to_string().contains("team 'C9' has a rating of NaN"));
+
+        assert!(matches!(adjust_ratings(f32::NAN, 1500f32, 32f32, 1f32, 0f32), Err(EloError::NonFiniteRating(_))));
+        // Both ratings are finite but the winner's new rating overflows.
+        assert!(matches!(adjust_ratings(f32::MAX, f32::MAX, 1e38f32, 1f32, 0f32), Err(EloError::NonFiniteRating(_))));
+        assert!(adjust_ratings(1500f32, 1500f32, 32f32, 1f32, 0f32).is_ok());
+
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), f32::MAX);
+        standings.insert("TSM".to_string(), f32::MAX);
+        let config = Configuration { k_brackets: vec![KBracket { start: 0, k: 1e38f32 }], ..test_config() };
+        let error = apply_match_result(&test_match("C9", "TSM"), &standings, &config).err().unwrap();
+        assert_eq!(error.to_string(), "the match between 'C9' and 'TSM' did not give a finite rating");
+
+        let config = Configuration { default_rating: Some(f32::INFINITY), home_advantage: Some(f32::NAN), ..test_config() };
+        let problems = config.validate().unwrap_err().problems;
+        assert_eq!(problems, vec!["default_rating must be a finite number, got inf", "home_advantage must be a finite number, got NaN"]);
+    }
+
+    #[test]
+    fn weighted_merge_blends_by_games_played() {
+        let mut first = StandingsFile::default();
+        first.standings.insert("C9".to_string(), 1600f32);
+        first.standings.insert("TSM".to_string(), 1500f32);
+        first.games_played.insert("C9".to_string(), 30);
+        let mut second = StandingsFile::default();
+        second.standings.insert("C9".to_string(), 1400f32);
+        second.standings.insert("TL".to_string(), 1450f32);
+        second.games_played.insert("C9".to_string(), 10);
+        second.games_played.insert("TL".to_string(), 4);
+
+        let merged = merge_standings(&first, &second, MergeStrategy::WeightedByGames);
+        assert_eq!(merged.standings["C9"], 1550f32);
+        assert_eq!(merged.games_played["C9"], 40);
+        assert_eq!(merged.standings["TSM"], 1500f32);
+        assert_eq!(merged.standings["TL"], 1450f32);
+        assert_eq!(merged.games_played["TL"], 4);
+
+        assert_eq!(merge_standings(&first, &second, MergeStrategy::Average).standings["C9"], 1500f32);
+        assert_eq!(merge_standings(&first, &second, MergeStrategy::Max).standings["C9"], 1600f32);
+        assert_eq!("weighted-by-games".parse(), Ok(MergeStrategy::WeightedByGames));
+        assert!("median".parse::<MergeStrategy>().is_err());
+    }
+
+    #[test]
+    fn flipped_result_reverses_rating_changes() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("TL".to_string(), 1500f32);
+        let results = vec![
+            test_match("TL", "TSM"),
+            MatchResult { winner_games: Some(2), loser_games: Some(1), ..test_match("C9", "TSM") },
+        ];
+        let config = test_config();
+
+        let mut flipped = results.clone();
+        flip_results(&mut flipped, &[1]).unwrap();
+        assert_eq!((flipped[1].winner.as_str(), flipped[1].winner_games), ("TSM", Some(1)));
+        assert_eq!((flipped[1].loser.as_str(), flipped[1].loser_games), ("C9", Some(2)));
+
+        let actual = apply_match_results(&results, &standings, &config).unwrap();
+        let what_if = apply_match_results(&flipped, &standings, &config).unwrap();
+        assert!(actual["C9"] > 1500f32 && what_if["C9"] < 1500f32);
+        assert!(what_if["TSM"] > actual["TSM"]);
+        assert_eq!(what_if["TL"], actual["TL"]);
+
+        let mut with_draw = vec![MatchResult { draw: true, ..test_match("C9", "TSM") }];
+        assert!(flip_results(&mut with_draw, &[0]).is_err());
+        assert!(flip_results(&mut flipped, &[0, 2]).is_err());
+        assert_eq!(flipped[0].winner, "TL");
+    }
+
+    #[test]
+    fn ndjson_matches_stream_into_standings() {
+        let stream = "{\"winner\": \"C9\", \"loser\": \"TSM\", \"series\": \"Bo1\"}\n\n{\"winner\": \"TL\", \"loser\": \"C9\", \"series\": \"Bo3\"}\n{\"winner\": \"TSM\", \"loser\": \"TL\", \"series\": \"Bo1\", \"rated\": false}\n";
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let config = Configuration { default_rating: Some(1500f32), ..test_config() };
+
+        let streamed: Result<Vec<MatchResult>, EloError> = NdjsonMatches::new(stream.as_bytes(), Path::new("-")).collect();
+        let streamed = streamed.unwrap();
+        assert_eq!(streamed.len(), 3);
+
+        let mut applied = 0;
+        let new_standings = apply_match_stream(NdjsonMatches::new(stream.as_bytes(), Path::new("-")), &standings, &GamesPlayed::new(), &config, |_, _| -> Result<(), EloError> {
+            applied += 1;
+            Ok(())
+        }).unwrap();
+        assert_eq!(applied, 2);
+        assert_eq!(new_standings, apply_match_results(&streamed, &standings, &config).unwrap());
+
+        let broken = "{\"winner\": \"C9\", \"loser\": \"TSM\", \"series\": \"Bo1\"}\n{\"winner\": \"C9\"}\n";
+        let error = apply_match_stream(NdjsonMatches::new(broken.as_bytes(), Path::new("feed.ndjson")), &standings, &GamesPlayed::new(), &config, |_, _| -> Result<(), EloError> { Ok(()) }).unwrap_err();
+        assert!(error.to_string().starts_with("could not parse 'feed.ndjson': line 2: "));
+    }
+
+    #[test]
+    fn margin_scores_weight_series_by_game_score() {
+        let config: Configuration = serde_json::from_str(r#"{
+            "bo1_score": 1, "bo3_score": 1.25, "bo5_score": 1.5,
+            "k_brackets": [{ "start": 0, "k": 40 }],
+            "series_margin_scores": { "Bo5": { "3-0": 2, "3-1": 1.5, "3-2": 1 } }
+        }"#).unwrap();
+        assert!(config.validate().is_ok());
+
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1000f32);
+        standings.insert("TSM".to_string(), 1000f32);
+        let bo5 = |winner_games, loser_games| MatchResult {
+            series: SeriesKind::Bo5,
+            winner_games,
+            loser_games,
+            ..test_match("C9", "TSM")
+        };
+        let gain = |result: MatchResult| apply_match_results(&[result], &standings, &config).unwrap()["C9"] - 1000f32;
+
+        assert_eq!(gain(bo5(Some(3), Some(0))), 60f32);
+        assert_eq!(gain(bo5(Some(3), Some(1))), 40f32);
+        assert_eq!(gain(bo5(Some(3), Some(2))), 20f32);
+        // Unlisted game scores and unscored series fall back to the flat score.
+        assert_eq!(gain(bo5(Some(4), Some(0))), 40f32 * margin_multiplier(Some(4), Some(0)));
+        assert_eq!(gain(bo5(None, None)), 40f32);
+    }
+
+    #[test]
+    fn validation_rejects_bad_margin_scores() {
+        let mut margins = HashMap::new();
+        margins.insert("3-0".to_string(), 0f32);
+        margins.insert("sweep".to_string(), 2f32);
+        let mut series_margin_scores = HashMap::new();
+        series_margin_scores.insert(SeriesKind::Bo5, margins);
+        let config = Configuration { series_margin_scores, ..test_config() };
+
+        assert_eq!(config.validate().unwrap_err().problems, vec![
+            "margin score for Bo5 3-0 must be in (0, 10], got 0".to_string(),
+            "margin score key 'sweep' for Bo5 must be a game score like 3-0".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn explanation_matches_the_update_it_explains() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1300f32);
+        standings.insert("TSM".to_string(), 1100f32);
+        let mut team_k_overrides = HashMap::new();
+        team_k_overrides.insert("TSM".to_string(), 30f32);
+        let config = Configuration { team_k_overrides, ..test_config() };
+        let result = MatchResult { series: SeriesKind::Bo3, ..test_match("TSM", "C9") };
+
+        let explanation = explain_match(&result, &standings, &GamesPlayed::new(), &config).unwrap();
+
+        assert_eq!(explanation.expected, get_expected_probabilities(1100f32, 1300f32));
+        assert_eq!(explanation.bracket_rating, 1200f32);
+        assert_eq!((explanation.winner_k_source, explanation.loser_k_source), (KSource::Override, KSource::Bracket));
+        assert_eq!((explanation.winner_base_k, explanation.loser_base_k), (30f32, 20f32));
+        assert_eq!(explanation.winner_score, config.bo3_score);
+
+        let update = get_match_update(&result, &standings, &GamesPlayed::new(), &config).unwrap();
+        assert_eq!((explanation.update.winner_delta, explanation.update.loser_delta), (update.winner_delta, update.loser_delta));
+
+        let text = explanation.to_string();
+        assert!(text.contains("k bracket: average rating 1200.0 falls in the bracket starting at 1200, k 20"));
+        assert!(text.contains(&format!("Expected probabilities: TSM {:.4}, C9 {:.4}", explanation.expected.0, explanation.expected.1)));
+    }
+
+    #[test]
+    fn matches_against_ignored_teams_are_skipped() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1000f32);
+        standings.insert("TSM".to_string(), 1000f32);
+        let config = Configuration {
+            ignored_teams: ["BYE".to_string()].iter().cloned().collect(),
+            default_rating: Some(1500f32),
+            ..test_config()
+        };
+        let results = [test_match("C9", "BYE"), test_match("BYE", "TSM"), test_match("TSM", "C9")];
+
+        let mut updates = Vec::new();
+        let new_standings = apply_match_results_inspect(&results, &standings, &config, |update, _| {
+            updates.push((update.winner.clone(), update.loser.clone()));
+            Ok::<(), EloError>(())
+        }).unwrap();
+
+        assert_eq!(updates, vec![("TSM".to_string(), "C9".to_string())]);
+        assert!(!new_standings.contains_key("BYE"));
+        assert_eq!(apply_match_result(&results[0], &standings, &config).unwrap(), standings);
+    }
+
+    #[test]
+    fn rating_diff_and_probability_round_trip() {
+        let steep = EloScale { base: 10f32, divisor: 200f32 };
+        for diff in [-800f32, -250f32, -1f32, 0f32, 30f32, 400f32, 1000f32].iter() {
+            let round_trip = rating_diff_for_probability(probability_for_rating_diff(*diff));
+            assert!((round_trip - diff).abs() < 0.5, "{} came back as {}", diff, round_trip);
+
+            let round_trip = rating_diff_for_probability_with_scale(probability_for_rating_diff_with_scale(*diff, steep), steep);
+            assert!((round_trip - diff).abs() < 0.5, "{} came back as {} with a divisor of 200", diff, round_trip);
+        }
+
+        assert_eq!(probability_for_rating_diff(150f32), get_expected_probabilities(1650f32, 1500f32).0);
+        assert_eq!(rating_diff_for_probability(1f32), f32::INFINITY);
+        assert!(rating_diff_for_probability(1.5f32).is_nan());
+    }
+
+    #[test]
+    fn layered_configs_only_change_overridden_fields() {
+        let dir = std::env::temp_dir();
+        let base_path = dir.join("elo_layered_configs_base.json");
+        let override_path = dir.join("elo_layered_configs_override.toml");
+        let nan_path = dir.join("elo_layered_configs_nan.yaml");
+        std::fs::write(&base_path, r#"{
+            "bo1_score": 1, "bo3_score": 1.25, "bo5_score": 1.5,
+            "k_brackets": [{ "start": 0, "k": 32 }, { "start": 2000, "k": 16 }],
+            "team_k_overrides": { "C9": 40 },
+            "default_rating": 1200
+        }"#).unwrap();
+        std::fs::write(&override_path, "bo3_score = 2.0\nk_brackets = [{ start = 0, k = 24 }]\n[team_k_overrides]\nTSM = 30.0\n").unwrap();
+        std::fs::write(&nan_path, "default_rating: .nan\n").unwrap();
+
+        let base = parse_layered_config(&[&base_path]).unwrap();
+        let layered = parse_layered_config(&[&base_path, &override_path]).unwrap();
+        let with_nan = parse_layered_config(&[&base_path, &nan_path]);
+        for path in [&base_path, &override_path, &nan_path].iter() {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        assert_eq!(layered.bo3_score, 2f32);
+        assert_eq!(layered.k_brackets.iter().map(|bracket| (bracket.start, bracket.k)).collect::<Vec<_>>(), vec![(0, 24f32)]);
+        assert_eq!(layered.team_k_overrides.get("C9"), Some(&40f32));
+        assert_eq!(layered.team_k_overrides.get("TSM"), Some(&30f32));
+
+        let mut expected = serde_json::to_value(&base).unwrap();
+        expected["bo3_score"] = serde_json::json!(2.0);
+        expected["k_brackets"] = serde_json::json!([{ "start": 0, "k": 24.0 }]);
+        expected["team_k_overrides"]["TSM"] = serde_json::json!(30.0);
+        assert_eq!(serde_json::to_value(&layered).unwrap(), expected);
+
+        assert!(matches!(with_nan, Err(EloError::ParseError { .. })));
+    }
+
+    #[test]
+    fn interned_results_match_the_name_keyed_path() {
+        let mut team_k_overrides = HashMap::new();
+        team_k_overrides.insert("TSM".to_string(), 30f32);
+        let mut aliases = HashMap::new();
+        aliases.insert("Cloud9".to_string(), "C9".to_string());
+        let config = Configuration {
+            team_k_overrides,
+            aliases,
+            default_rating: Some(1300f32),
+            provisional_games: 3,
+            provisional_k: Some(60f32),
+            home_advantage: Some(50f32),
+            ..test_config()
+        };
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1400f32);
+        standings.insert("CLG".to_string(), 900f32);
+
+        let teams = ["C9", "Cloud9", "TSM", "CLG", "TL", "FLY"];
+        let results: Vec<MatchResult> = (0..60)
+            .map(|i| MatchResult {
+                series: if i % 3 == 0 { SeriesKind::Bo3 } else { SeriesKind::Bo1 },
+                draw: i % 7 == 0,
+                home: (i % 4 == 0).then(|| teams[i % teams.len()].to_string()),
+                rated: i % 11 != 0,
+                ..test_match(teams[i % teams.len()], teams[(i * 5 + 2) % teams.len()])
+            })
+            .filter(|result| config.canonical_team(&result.winner) != config.canonical_team(&result.loser))
+            .collect();
+
+        let name_keyed = apply_match_results_inspect(&results, &standings, &config, |_, _| Ok::<(), EloError>(())).unwrap();
+        let interned = apply_match_results(&results, &standings, &config).unwrap();
+
+        assert_eq!(interned, name_keyed);
+        assert!(!interned.contains_key("Cloud9"));
+
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("TSM".to_string(), 5);
+        let mut name_keyed_updates = Vec::new();
+        let name_keyed = apply_match_results_inspect_with_games(&results, &standings, &games_played, &config, |update, _| {
+            name_keyed_updates.push(update.clone());
+            Ok::<(), EloError>(())
+        }).unwrap();
+        let mut interned_updates = Vec::new();
+        let interned = apply_match_results_inspect_updates(&results, &standings, &games_played, &config, |update| {
+            interned_updates.push(update.clone());
+            Ok::<(), EloError>(())
+        }).unwrap();
+        assert_eq!(interned, name_keyed);
+        assert_eq!(interned_updates, name_keyed_updates);
+        let error = apply_match_results(&[test_match("C9", "Cloud9")], &standings, &config).unwrap_err();
+        assert!(matches!(error, EloError::InMatch { source, .. } if matches!(*source, EloError::SelfMatch(ref team) if team == "C9")));
+    }
+
+    #[test]
+    fn failing_match_is_reported_with_its_index() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let results = [
+            test_match("C9", "TSM"),
+            test_match("TSM", "C9"),
+            MatchResult { date: Some("2021-06-12".to_string()), ..test_match("C9", "CLG") },
+            test_match("TSM", "C9"),
+        ];
+        let config = Configuration { default_rating: Some(f32::INFINITY), ..test_config() };
+
+        let name_keyed = apply_match_results_inspect(&results, &standings, &config, |_, _| Ok::<(), EloError>(())).unwrap_err();
+        let interned = apply_match_results(&results, &standings, &config).unwrap_err();
+        for error in [name_keyed, interned].iter() {
+            match error {
+                EloError::InMatch { index, result, source } => {
+                    assert_eq!(*index, 2);
+                    assert_eq!((result.winner.as_str(), result.loser.as_str()), ("C9", "CLG"));
+                    assert!(matches!(**source, EloError::NonFiniteRating(_)));
+                },
+                other => panic!("expected the failing match, got {}", other),
+            }
+            assert!(error.to_string().starts_with("match 2 (C9 beat CLG in a Bo1 on 2021-06-12): "));
+        }
+    }
+
+    #[test]
+    fn games_of_a_series_collapse_into_one_result() {
+        let game = |series_id: &str, winner: &str, loser: &str| GameResult {
+            series_id: series_id.to_string(),
+            winner: winner.to_string(),
+            loser: loser.to_string(),
+            series: None,
+            date: Some("2021-06-12".to_string()),
+        };
+        let games = [
+            game("final", "C9", "TSM"),
+            game("semi", "CLG", "TL"),
+            game("final", "TSM", "C9"),
+            game("final", "TSM", "C9"),
+        ];
+
+        let results = group_games_into_series(&games).unwrap();
+
+        assert_eq!(results.len(), 2);
+        let final_series = &results[0];
+        assert_eq!((final_series.winner.as_str(), final_series.loser.as_str()), ("TSM", "C9"));
+        assert_eq!(final_series.series, SeriesKind::Bo3);
+        assert_eq!((final_series.winner_games, final_series.loser_games), (Some(2), Some(1)));
+        assert_eq!(final_series.date.as_deref(), Some("2021-06-12"));
+        assert!(!final_series.draw);
+        assert_eq!((results[1].winner.as_str(), results[1].series), ("CLG", SeriesKind::Bo1));
+
+        let split = group_games_into_series(&[game("split", "C9", "TSM"), game("split", "TSM", "C9")]).unwrap();
+        assert!(split[0].draw);
+        assert_eq!(split[0].series, SeriesKind::Custom(2));
+
+        let mixed = group_games_into_series(&[game("final", "C9", "TSM"), game("final", "C9", "CLG")]);
+        assert!(mixed.unwrap_err().contains("series 'final' has a game between C9 and CLG"));
+    }
+
+    #[test]
+    fn repeated_matchups_move_ratings_less() {
+        let standings: Standings = [("C9".to_string(), 1000f32), ("TSM".to_string(), 1000f32)].iter().cloned().collect();
+        let results: Vec<MatchResult> = (0..10).map(|_| test_match("C9", "TSM")).collect();
+        let gains = |config: &Configuration| {
+            let mut gains = Vec::new();
+            apply_match_results_inspect(&results, &standings, config, |update, _| {
+                gains.push(update.winner_delta);
+                Ok::<(), EloError>(())
+            })
+            .unwrap();
+            gains
+        };
+
+        let undamped = gains(&test_config());
+        let mut config = test_config();
+        config.repeat_matchup_decay = Some(0.8);
+        let damped = gains(&config);
+
+        assert_eq!(damped[0], undamped[0]);
+        assert!(damped[9] < damped[0]);
+        assert!(damped[9] < undamped[9]);
+        assert_eq!(apply_match_results(&results, &standings, &config).unwrap(), apply_match_results_inspect(&results, &standings, &config, |_, _| Ok::<(), EloError>(())).unwrap());
+
+        config.repeat_matchup_decay = Some(0f32);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn performance_totals_expected_and_actual_wins() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1100f32);
+        standings.insert("CLG".to_string(), 1500f32);
+        let config = test_config();
+        let mut performance = Performance::default();
+
+        // C9 is expected to win 10 in 11 against TSM and 1 in 2 against CLG.
+        performance.record(&get_match_update(&test_match("TSM", "C9"), &standings, &GamesPlayed::new(), &config).unwrap());
+        performance.record(&get_match_update(&test_match("C9", "CLG"), &standings, &GamesPlayed::new(), &config).unwrap());
+        performance.record(&get_match_update(&MatchResult { draw: true, ..test_match("TSM", "CLG") }, &standings, &GamesPlayed::new(), &config).unwrap());
+
+        let c9 = performance.teams["C9"];
+        assert_eq!(c9.matches, 2);
+        assert!((c9.expected_wins - (10f32 / 11f32 + 0.5f32)).abs() < 1e-5);
+        assert_eq!(c9.actual_wins, 1f32);
+        let tsm = performance.teams["TSM"];
+        assert!((tsm.expected_wins - 2f32 / 11f32).abs() < 1e-5);
+        assert_eq!(tsm.actual_wins, 1.5f32);
+        let clg = performance.teams["CLG"];
+        assert!((clg.expected_wins - (0.5f32 + 10f32 / 11f32)).abs() < 1e-5);
+        assert_eq!(clg.actual_wins, 0.5f32);
+
+        let report = performance.to_string();
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("TSM: 2 matches, expected 0.18 wins, won 1.5"));
+        assert!(lines[2].starts_with("CLG:"));
+    }
+
+    #[test]
+    fn other_rating_systems_can_be_plugged_in() {
+        // Moves the winner up and the loser down by a fixed step, no matter the ratings.
+        struct FixedStep(f32);
+
+        impl RatingSystem for FixedStep {
+            fn expected(&self, _: f32, _: f32) -> (f32, f32) {
+                (0.5f32, 0.5f32)
+            }
+
+            fn update(&self, rating_a: f32, rating_b: f32, score_a: f32, score_b: f32) -> Result<(f32, f32), EloError> {
+                Ok((rating_a + self.0 * (score_a - score_b), rating_b + self.0 * (score_b - score_a)))
+            }
+        }
+
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1100f32);
+        let config = test_config();
+
+        let new_standings = apply_match_result_with_system(&test_match("C9", "TSM"), &standings, &config, &FixedStep(10f32)).unwrap();
+        assert_eq!(new_standings["C9"], 1510f32);
+        assert_eq!(new_standings["TSM"], 1090f32);
+        let drawn = apply_match_result_with_system(&MatchResult { draw: true, ..test_match("C9", "TSM") }, &standings, &config, &FixedStep(10f32)).unwrap();
+        assert_eq!(drawn, standings);
+        assert_eq!(FixedStep(10f32).expected(1500f32, 1100f32), (0.5f32, 0.5f32));
+
+        // The built-in ELO gives the same ratings either way for a plain match.
+        let elo = Elo { configuration: &config };
+        assert_eq!(elo.expected(1500f32, 1100f32), config.expected_probabilities(1500f32, 1100f32));
+        assert_eq!(
+            apply_match_result_with_system(&test_match("TSM", "C9"), &standings, &config, &elo).unwrap(),
+            apply_match_result(&test_match("TSM", "C9"), &standings, &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn strictness_decides_what_happens_to_warnings() {
+        let warnings = cross_reference_teams(&[test_match("C9", "FLY")], &[("C9".to_string(), 1500f32)].iter().cloned().collect()).warnings();
+        assert_eq!(warnings, vec!["teams in matches but not in standings: FLY".to_string()]);
+
+        assert!(Strictness::Off.check(&warnings).unwrap().is_empty());
+        assert_eq!(Strictness::Warn.check(&warnings).unwrap(), &warnings[..]);
+        assert_eq!(Strictness::Error.check(&warnings), Err("teams in matches but not in standings: FLY".to_string()));
+        assert!(Strictness::Error.check(&[]).unwrap().is_empty());
+
+        assert_eq!("Error".parse(), Ok(Strictness::Error));
+        assert!("loud".parse::<Strictness>().is_err());
+    }
+
+    #[test]
+    fn normalizing_moves_the_mean_and_keeps_the_differences() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1830f32);
+        standings.insert("TSM".to_string(), 1612f32);
+        standings.insert("CLG".to_string(), 1395f32);
+
+        let normalized = normalize_standings(&standings, 1500f32);
+        assert!((mean_rating(&normalized).unwrap() - 1500f32).abs() < 1e-3);
+        for (a, b) in [("C9", "TSM"), ("C9", "CLG"), ("TSM", "CLG")] {
+            assert!(((normalized[a] - normalized[b]) - (standings[a] - standings[b])).abs() < 1e-3);
+        }
+        assert!(normalize_standings(&Standings::new(), 1500f32).is_empty());
+    }
+
+    #[test]
+    fn series_kinds_read_in_any_case_and_round_trip() {
+        let parse = |json: &str| serde_json::from_str::<SeriesKind>(json).unwrap();
+        assert_eq!(parse(r#""bo3""#), SeriesKind::Bo3);
+        assert_eq!(parse(r#""BO5""#), SeriesKind::Bo5);
+        assert_eq!(parse(r#""Bo1""#), SeriesKind::Bo1);
+        assert_eq!(parse(r#""bo9""#), SeriesKind::Custom(9));
+        assert_eq!(parse(r#"{"Custom": 9}"#), SeriesKind::Custom(9));
+        assert!(serde_json::from_str::<SeriesKind>(r#""best of three""#).is_err());
+        assert!(serde_json::from_str::<SeriesKind>(r#"{"Custom": 0}"#).is_err());
+
+        for series in [SeriesKind::Bo1, SeriesKind::Bo3, SeriesKind::Bo5, SeriesKind::Bo7, SeriesKind::Custom(3), SeriesKind::Custom(9)] {
+            let json = serde_json::to_string(&series).unwrap();
+            assert_eq!(parse(&json), series);
+        }
+        assert_eq!(serde_json::to_string(&SeriesKind::Bo3).unwrap(), r#""Bo3""#);
+
+        let config: Configuration = toml::from_str("bo1_score = 1\nbo3_score = 1.25\nbo5_score = 1.5\nk_brackets = [{ start = 0, k = 32 }]\n[series_margin_scores.bo3]\n2-0 = 1.4\n").unwrap();
+        assert_eq!(config.margin_score(SeriesKind::Bo3, Some(2), Some(0)), Some(1.4f32));
+    }
+
+    #[test]
+    fn checking_matches_finds_each_kind_of_problem() {
+        let matches: Vec<UncheckedMatch> = serde_json::from_str(r#"[
+            {"winner": "C9", "loser": "TSM", "series": "bo3"},
+            {"winner": "FLY", "loser": "TSM", "series": "Bo1"},
+            {"winner": "C9", "loser": "TSM", "series": "best of 3"},
+            {"winner": "C9", "loser": "Cloud9", "series": {"Custom": 9}},
+            {"winner": "FNC", "loser": "FNC", "series": "Bo1", "rated": false}
+        ]"#).unwrap();
+        let mut config = test_config();
+        config.aliases.insert("Cloud9".to_string(), "C9".to_string());
+        let standings: Standings = [("C9".to_string(), 1500f32), ("TSM".to_string(), 1400f32)].iter().cloned().collect();
+
+        let check = check_matches(&matches, &standings, &config);
+        assert_eq!(check.matches, 5);
+        assert_eq!(check.missing_teams, vec![(1, "FLY".to_string())]);
+        assert_eq!(check.invalid_series, vec![(2, r#""best of 3""#.to_string())]);
+        assert_eq!(check.self_matches, vec![(3, "C9".to_string())]);
+        assert!(!check.passed());
+        assert!(check.to_string().contains("Missing teams: 1\n  match 1: FLY\n"));
+
+        config.default_rating = Some(1500f32);
+        assert!(check_matches(&matches, &standings, &config).missing_teams.is_empty());
+    }
+
+    #[test]
+    fn interpolated_k_changes_smoothly_across_a_boundary() {
+        let k_brackets = test_config().k_brackets;
+        assert_eq!(scaling_for_rating(1199f32, &k_brackets), Some(40f32));
+        assert_eq!(scaling_for_rating(1200f32, &k_brackets), Some(20f32));
+        let just_below = interpolated_scaling_for_rating(1199f32, &k_brackets).unwrap();
+        let at = interpolated_scaling_for_rating(1200f32, &k_brackets).unwrap();
+        assert!((just_below - (20f32 + 20f32 / 1200f32)).abs() < 1e-4);
+        assert_eq!(at, 20f32);
+        assert!((interpolated_scaling_for_rating(600f32, &k_brackets).unwrap() - 30f32).abs() < 1e-4);
+        assert_eq!(interpolated_scaling_for_rating(3000f32, &k_brackets), Some(10f32));
+        assert_eq!(interpolated_scaling_for_rating(-50f32, &k_brackets), Some(40f32));
+
+        let standings: Standings = [("C9".to_string(), 600f32), ("TSM".to_string(), 600f32)].iter().cloned().collect();
+        let stepped = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &test_config()).unwrap();
+        let config = Configuration { bracket_interpolation: true, ..test_config() };
+        let interpolated = get_match_update(&test_match("C9", "TSM"), &standings, &GamesPlayed::new(), &config).unwrap();
+        assert_eq!(stepped.winner_k, 40f32);
+        assert!((interpolated.winner_k - 30f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn config_flags_win_over_the_environment() {
+        let flags = vec![PathBuf::from("base.json"), PathBuf::from("override.json")];
+        assert_eq!(resolve_config_paths(flags.clone(), Some(OsString::from("env.json"))), Some(flags));
+        assert_eq!(resolve_config_paths(Vec::new(), Some(OsString::from("env.json"))), Some(vec![PathBuf::from("env.json")]));
+        assert_eq!(resolve_config_paths(Vec::new(), Some(OsString::new())), None);
+        assert_eq!(resolve_config_paths(Vec::new(), None), None);
+    }
+
+    #[test]
+    fn rank_of_shares_positions_between_ties() {
+        let standings: Standings = [("C9", 1500f32), ("TSM", 1500f32), ("CLG", 1500f32), ("TL", 1400f32), ("FLY", 1700f32)]
+            .iter()
+            .map(|(team, rating)| (team.to_string(), *rating))
+            .collect();
+
+        assert_eq!(rank_of(&standings, "FLY"), Some(1));
+        for team in ["C9", "TSM", "CLG"] {
+            assert_eq!(rank_of(&standings, team), Some(2));
+        }
+        assert_eq!(rank_of(&standings, "TL"), Some(5));
+        assert_eq!(rank_of(&standings, "Cloud9"), None);
+
+        let all_tied: Standings = [("C9".to_string(), 1500f32), ("TSM".to_string(), 1500f32)].iter().cloned().collect();
+        assert_eq!(rank_of(&all_tied, "C9"), Some(1));
+        assert_eq!(rank_of(&all_tied, "TSM"), Some(1));
+        assert_eq!(rank_of(&Standings::new(), "C9"), None);
+    }
+
+    #[test]
+    fn rating_changes_add_up_to_the_new_ratings() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1512.34f32);
+        standings.insert("TSM".to_string(), 1450f32);
+        standings.insert("CLG".to_string(), 1400f32);
+        let config = Configuration { default_rating: Some(1450f32), ..test_config() };
+        let results = vec![test_match("C9", "TSM"), test_match("TL", "C9"), test_match("CLG", "TSM")];
+        let new_standings = apply_match_results(&results, &standings, &config).unwrap();
+
+        let changes = standings_with_previous(&standings, &new_standings, &config);
+        assert_eq!(changes.len(), new_standings.len());
+        for (team, change) in &changes {
+            assert_eq!(change.new, new_standings[team]);
+            assert!((change.old + change.delta - change.new).abs() < 1e-3, "{}: {:?}", team, change);
+        }
+        assert_eq!(changes["C9"].old, standings["C9"]);
+        assert_eq!(changes["TL"].old, 1450f32);
+
+        let rounded = changes["C9"].rounded(1);
+        assert_eq!(round_to_places(rounded.old + rounded.delta, 1), rounded.new);
+        assert_eq!(rounded.new, round_standings(&new_standings, 1)["C9"]);
+    }
+
+    #[test]
+    fn duplicate_bracket_starts_fail_validation_and_the_last_one_wins() {
+        let brackets = vec![
+            KBracket { start: 0, k: 40f32 },
+            KBracket { start: 1200, k: 20f32 },
+            KBracket { start: 1200, k: 30f32 },
+            KBracket { start: 2400, k: 10f32 },
+        ];
+        let config = Configuration { k_brackets: brackets.clone(), ..test_config() };
+        assert_eq!(config.validate().unwrap_err().problems, vec!["more than one k bracket starts at 1200"]);
+
+        let reversed: Vec<KBracket> = brackets.iter().rev().copied().collect();
+        assert_eq!(scaling_for_rating(1500f32, &brackets), Some(30f32));
+        assert_eq!(scaling_for_rating(1500f32, &reversed), Some(20f32));
+        assert_eq!(interpolated_scaling_for_rating(1000f32, &brackets), Some(40f32 + (30f32 - 40f32) * 1000f32 / 1200f32));
+
+        let (_, bracket) = bracket_for_match(1500f32, 1500f32, &config);
+        assert_eq!(bracket.map(|bracket| (bracket.start, bracket.k)), Some((1200, 30f32)));
+        let lowest = Configuration { k_brackets: vec![KBracket { start: 100, k: 40f32 }, KBracket { start: 100, k: 50f32 }], ..test_config() };
+        assert_eq!(lowest.lowest_bracket().map(|bracket| bracket.k), Some(50f32));
+    }
+
+    #[test]
+    fn tuning_finds_the_k_with_the_lowest_log_loss() {
+        let season = crate::fixtures::generate_season(16, 1500, 11);
+        let config = crate::fixtures::season_configuration();
+        let log_loss = |k| calibration_for_k(&season.matches, &season.standings, &GamesPlayed::new(), &config, k).unwrap().log_loss().unwrap();
+
+        let grid: Vec<(f32, f32)> = (1..=100).map(|step| (step as f32, log_loss(step as f32))).collect();
+        let (grid_best, _) = *grid.iter().min_by(|a, b| a.1.partial_cmp(&b.1).unwrap()).unwrap();
+        assert!(grid_best > 1f32 && grid_best < 100f32, "the best k should be inside the range, got {}", grid_best);
+
+        let tuning = tune_k(&season.matches, &season.standings, &GamesPlayed::new(), &config, 0.5f32, 100f32).unwrap();
+        assert!((tuning.k - grid_best).abs() <= 1f32, "tuned {} but the grid found {}", tuning.k, grid_best);
+        assert!(tuning.calibration.log_loss().unwrap() <= log_loss(grid_best) + 1e-4);
+        assert_eq!(tuning.calibration.matches, 1500);
+        assert!(tuning.calibration.log_loss().unwrap() < grid[0].1 && tuning.calibration.log_loss().unwrap() < grid[99].1);
+    }
+
+    #[test]
+    fn tuning_rejects_bad_ranges_and_nothing_to_predict() {
+        let season = crate::fixtures::generate_season(4, 20, 1);
+        let config = crate::fixtures::season_configuration();
+        let tune = |results: &[MatchResult], min_k, max_k| tune_k(results, &season.standings, &GamesPlayed::new(), &config, min_k, max_k);
+
+        for (min_k, max_k) in [(0f32, 10f32), (20f32, 10f32), (5f32, 5f32), (1f32, f32::INFINITY), (f32::NAN, 10f32)] {
+            assert!(matches!(tune(&season.matches, min_k, max_k), Err(EloError::InvalidConfig(_))), "{} to {}", min_k, max_k);
+        }
+
+        let unrated: Vec<MatchResult> = season.matches.iter().cloned().map(|result| MatchResult { rated: false, ..result }).collect();
+        assert!(matches!(tune(&unrated, 1f32, 100f32), Err(EloError::InvalidConfig(_))));
+        assert!(matches!(tune(&[], 1f32, 100f32), Err(EloError::InvalidConfig(_))));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn old_scaling_name_still_averages_the_ratings() {
+        let brackets = test_brackets();
+        for (rating1, rating2) in [(1000f32, 1500f32), (2000f32, 2900f32), (100f32, 100f32)] {
+            assert_eq!(scaling_for_rating_difference(rating1, rating2, &brackets), scaling_for_combined_rating(rating1, rating2, &brackets));
+        }
+    }
+}
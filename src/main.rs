@@ -1,168 +1,61 @@
-use serde;
-use serde_json;
-use std::collections::HashMap;
+use clap::{Arg, App, ArgMatches, SubCommand};
+use elo::{
+    apply_match_results_inspect_updates, apply_match_results_inspect_with_games, apply_match_stream,
+    build_run_report, check_matches, compare_standings, cross_reference_canonical_teams,
+    cross_reference_team_names, decay_standings, explain_match, filter_by_date, filter_by_division,
+    flip_results, format_leaderboard_with_names, format_movers, format_team_report,
+    get_probabilities_from_standings, group_games_into_series, is_ndjson, merge_standings,
+    normalize_standings, open_ndjson_matches, parse_game_results_from_path, parse_layered_config,
+    parse_layered_config_value, parse_match_results_from_path, parse_standings_file_from_path,
+    parse_standings_from_path, parse_team_list, parse_type_from_path,
+    parse_unchecked_matches_from_path, parse_unchecked_ndjson_matches_from_path, probability_matrix,
+    resolve_config_paths, round_standings, sort_by_date, standings_from_records,
+    standings_from_team_list, standings_with_previous, standings_with_uncertainty, summarize_run,
+    to_sorted_json_string, top_standings, tune_k, undo_match_result, weight_by_recency,
+    write_standings_csv, write_standings_csv_to_writer, write_standings_file_to_path,
+    write_standings_to_path, write_type_to_path, CONFIG_ENV_VAR, Calibration, Configuration,
+    DEFAULT_RATING, EloError, GamesPlayed, MatchResult, MatchUpdate, MergeStrategy, Performance,
+    Standings, StandingsFile, Strictness, UncheckedMatch, WinLossRecord, Wins,
+};
+use elo::repl::run_repl;
+use elo::schema::{validate_file, validate_value, InputKind};
+use elo::simulate::{simulate_season, Matchup};
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufReader, Write};
-use std::path::Path;
-use clap::{Arg, App, SubCommand};
-
-type Standings = HashMap<String, f32>;
-
-#[derive(serde::Deserialize, std::marker::Copy, std::clone::Clone)]
-enum SeriesKind {
-    Bo1,
-    Bo3,
-    Bo5,
-}
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process;
 
-#[derive(serde::Deserialize)]
-struct MatchResult {
-    winner: String,
-    loser: String,
-    series: SeriesKind,
-}
+/// `update` options that need every match before the first is applied, matches are read in full
+/// rather than streamed when any of them is given.
+const NEEDS_ALL_MATCHES: [&str; 9] = ["flip", "since", "until", "sort-by-date", "half-life", "strict", "summary", "report", "tune"];
 
-#[derive(serde::Deserialize, std::marker::Copy, std::clone::Clone)]
-struct KBracket {
-    start: u32,
-    k: f32
+/// The config files given with `--config`, or in `ELO_CONFIG`, see `resolve_config_paths`.
+fn config_paths_for(sub_m: &ArgMatches) -> Option<Vec<PathBuf>> {
+    let flag_paths = sub_m.values_of("config").into_iter().flatten().map(PathBuf::from).collect();
+    resolve_config_paths(flag_paths, env::var_os(CONFIG_ENV_VAR))
 }
 
-#[derive(serde::Deserialize, std::clone::Clone)]
-struct Configuration {
-    bo1_score: f32,
-    bo3_score: f32,
-    bo5_score: f32,
-    k_brackets: Vec<KBracket>
-}
+/// Prints the chance each team wins, using the `elo_base` and `elo_divisor` of `config` when given.
+fn print_prediction(standings: &HashMap<String, f32>, config: Option<&Configuration>, team_a: &str, team_b: &str) -> Result<(), Box<dyn Error>> {
+    let canonical_team = |team| config.map_or(team, |config| config.canonical_team(team));
+    let team_a = canonical_team(team_a);
+    let team_b = canonical_team(team_b);
 
-fn get_series_win_weight_from_config(configuration: Configuration) -> impl Fn(SeriesKind) -> f32 {
-    let series_fn = move |series| {
-        match series {
-            SeriesKind::Bo1 => configuration.bo1_score,
-            SeriesKind::Bo3 => configuration.bo3_score,
-            SeriesKind::Bo5 => configuration.bo5_score,
-        }
+    let (p_a, p_b) = get_probabilities_from_standings(standings, team_a, team_b)?;
+    let (p_a, p_b) = match config {
+        Some(config) => config.expected_probabilities(standings[team_a], standings[team_b]),
+        None => (p_a, p_b),
     };
-
-    return series_fn;
-}
-
-fn get_expected_probabilities(rating1: f32, rating2: f32) -> (f32, f32) {
-    let p1 = 1f32 / (1f32 + f32::powf(10f32, (rating2 - rating1) / 400f32));
-    let p2 = 1f32 / (1f32 + f32::powf(10f32, (rating1 - rating2) / 400f32));
-
-    return (p1, p2);
-}
-
-fn scaling_for_rating(rating: f32, k_brackets: &Vec<KBracket>) -> Option<f32> {
-    let mut k_brackets_sorted: Vec<KBracket> = k_brackets.clone();
-    k_brackets_sorted.sort_by_key(|bracket| bracket.start);
-
-    for bracket in k_brackets_sorted.iter() {
-        if rating >= bracket.start as f32 {
-            return Some(bracket.k)
-        }
-    };
-
-   None 
-}
-
-fn combine_ratings(rating1: f32, rating2: f32) -> f32 {
-    (rating1 + rating2) / 2f32
-}
-
-fn scaling_for_rating_difference(rating1: f32, rating2: f32, k_brackets: &Vec<KBracket>) -> Option<f32> {
-    let bracket_rating = combine_ratings(rating1, rating2);
-    scaling_for_rating(bracket_rating, k_brackets)
-}
-
-fn adjust_ratings(
-    rating1: f32,
-    rating2: f32,
-    k: f32,
-    actual_score1: f32,
-    actual_score2: f32,
-) -> (f32, f32) {
-    let expected_probabilities = get_expected_probabilities(rating1, rating2);
-
-    let new_rating1 = rating1 + k * (actual_score1 - expected_probabilities.0);
-    let new_rating2 = rating2 + k * (actual_score2 - expected_probabilities.1);
-
-    return (new_rating1, new_rating2);
-}
-
-fn apply_match_result(result: &MatchResult, standings: &Standings, series_win_weight:  &impl Fn(SeriesKind) -> f32, k_brackets: &Vec<KBracket>) -> Option<Standings> {
-    let winner_rating = standings.get(&result.winner)?;
-    let loser_rating = standings.get(&result.loser)?;
-
-    let mut new_standings = standings.clone();
-    let new_ratings = adjust_ratings(
-        *winner_rating,
-        *loser_rating,
-        scaling_for_rating_difference(*winner_rating, *loser_rating, k_brackets)?,
-        series_win_weight(result.series),
-        0f32,
-    );
-    new_standings.insert(result.winner.clone(), new_ratings.0);
-    new_standings.insert(result.loser.clone(), new_ratings.1);
-
-    return Some(new_standings);
-}
-
-fn apply_match_results(results: &Vec<MatchResult>, standings: &Standings, k_brackets: &Vec<KBracket>, series_win_weight: &impl Fn(SeriesKind) -> f32) -> Option<Standings> {
-    results
-        .iter()
-        .fold(Some(standings.clone()), |acc, result| match acc {
-            Some(standing) => apply_match_result(result, &standing, series_win_weight, k_brackets),
-            None => None,
-        })
-}
-
-fn get_probabilities_from_standings(standings: &Standings, team_a: &str, team_b: &str) -> Option<(f32, f32)> {
-    let rating_a = standings.get(team_a)?;
-    let rating_b = standings.get(team_b)?;
-
-    return Some(get_expected_probabilities(*rating_a, *rating_b));
-}
-
-fn parse_type_from_path<'a, T>(path: &Path) -> Result<T, Box<dyn Error>> 
-where
-    for<'de> T: serde::Deserialize<'de> + 'a
-{
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let data = serde_json::from_reader(reader)?;
-    Ok(data)
-}
-
-fn parse_standings_from_path(path: &Path) -> Result<Standings, Box<dyn Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let standings = serde_json::from_reader(reader)?;
-    Ok(standings)
+    println!("{}: {:.1}%", team_a, p_a * 100f32);
+    println!("{}: {:.1}%", team_b, p_b * 100f32);
+    Ok(())
 }
 
-fn parse_match_results_from_path(path: &Path) -> Result<Vec<MatchResult>, Box<dyn Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let results = serde_json::from_reader(reader)?;
-    Ok(results)
-}
-
-fn write_standings_to_path(path: &Path, standings: &Standings) -> Result<(), Box<dyn Error>> {
-    let mut file = File::create(path)?;
-    let standings_string = serde_json::to_string_pretty(standings)?;
-    file.write_all(standings_string.as_bytes())?;
-
-    return Ok(());
-}
-
-fn main() {
+fn run() -> Result<(), Box<dyn Error>> {
     let matches = App::new("ELO System")
                           .version("1.0")
                           .author("Steven Pham")
@@ -173,44 +66,448 @@ fn main() {
                                   .short("c")
                                   .long("config")
                                   .value_name("FILE")
-                                  .help("Path to config file, default is `config.toml`")
-                                  .takes_value(true))
+                                  .help("Path to a JSON or TOML config file, default is the path in ELO_CONFIG or else `config.json`, repeat to layer override files over it")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1))
                               .arg(Arg::with_name("standings")
                                   .short("s")
                                   .long("standings")
                                   .value_name("FILE")
-                                  .help("Path to standings file")
+                                  .help("Path to standings file, `-` reads from stdin")
                                   .takes_value(true)
                                   .required(true))
                               .arg(Arg::with_name("matches")
                                   .short("m")
                                   .long("matches")
                                   .value_name("FILE")
-                                  .help("Path to matches file")
+                                  .help("Path to matches file, `-` reads from stdin. Can be repeated or comma separated to apply several files in order")
                                   .takes_value(true)
-                                  .required(true))
+                                  .multiple(true)
+                                  .number_of_values(1)
+                                  .required_unless_one(&["match-winner", "predict"]))
+                              .arg(Arg::with_name("group-games")
+                                  .long("group-games")
+                                  .help("Reads the matches files as single games with a series_id and rates each series once")
+                                  .conflicts_with_all(&["matches-format", "check"]))
+                              .arg(Arg::with_name("matches-format")
+                                  .long("matches-format")
+                                  .value_name("FORMAT")
+                                  .help("Reads every matches file as NDJSON, one match per line, whatever its extension. NDJSON matches are applied as they are read")
+                                  .takes_value(true)
+                                  .possible_values(&["ndjson"]))
+                              .arg(Arg::with_name("match-winner")
+                                  .long("match-winner")
+                                  .value_name("TEAM")
+                                  .help("Applies a single match won by TEAM instead of reading a matches file")
+                                  .takes_value(true)
+                                  .requires("match-loser")
+                                  .conflicts_with("matches"))
+                              .arg(Arg::with_name("match-loser")
+                                  .long("match-loser")
+                                  .value_name("TEAM")
+                                  .help("Loser of the match given with --match-winner")
+                                  .takes_value(true)
+                                  .requires("match-winner"))
+                              .arg(Arg::with_name("match-series")
+                                  .long("match-series")
+                                  .value_name("SERIES")
+                                  .help("Series of the match given with --match-winner, such as bo3, default is bo1")
+                                  .takes_value(true)
+                                  .requires("match-winner"))
+                              .arg(Arg::with_name("explain")
+                                  .long("explain")
+                                  .help("Prints a step-by-step breakdown of the match given with --match-winner to stderr")
+                                  .requires("match-winner"))
                               .arg(Arg::with_name("output")
                                   .short("o")
                                   .long("output")
                                   .value_name("FILE")
-                                  .help("Path to output standings")
+                                  .help("Path to output standings, `-` writes to stdout")
                                   .takes_value(true)
-                                  .required(true)))
+                                  .required_unless_one(&["dry-run", "validate-only", "check", "tune", "predict"]))
+                              .arg(Arg::with_name("predict")
+                                  .long("predict")
+                                  .value_names(&["TEAM_A", "TEAM_B"])
+                                  .help("Prints the chance TEAM_A and TEAM_B each win a match given the standings, without applying matches or writing standings")
+                                  .takes_value(true)
+                                  .number_of_values(2)
+                                  .conflicts_with_all(&["matches", "match-winner", "output"]))
+                              .arg(Arg::with_name("sort-by-date")
+                                  .long("sort-by-date")
+                                  .help("Sorts the matches by date before applying them"))
+                              .arg(Arg::with_name("flip")
+                                  .long("flip")
+                                  .value_name("INDEX")
+                                  .help("Swaps the winner and loser of the match at INDEX, counting from 0 across every matches file, to see what the standings would be had it gone the other way. Can be repeated or comma separated")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1))
+                              .arg(Arg::with_name("since")
+                                  .long("since")
+                                  .value_name("DATE")
+                                  .help("Only applies matches played on or after DATE")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("until")
+                                  .long("until")
+                                  .value_name("DATE")
+                                  .help("Only applies matches played on or before DATE")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("include-undated")
+                                  .long("include-undated")
+                                  .help("Keeps matches without a date when filtering by --since or --until"))
+                              .arg(Arg::with_name("half-life")
+                                  .long("half-life")
+                                  .value_name("N")
+                                  .help("Halves the k of a match for every N matches, or N days when every match has a date, it was played before the last match")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("calibrate")
+                                  .long("calibrate")
+                                  .help("Prints the Brier score and log loss of the predictions made for every match to stderr"))
+                              .arg(Arg::with_name("tune")
+                                  .long("tune")
+                                  .value_name("MIN,MAX")
+                                  .help("Searches MIN to MAX, 1 to 100 by default, for the single k with the lowest log loss on the matches and prints it with its scores instead of writing standings")
+                                  .takes_value(true)
+                                  .min_values(0)
+                                  .max_values(1))
+                              .arg(Arg::with_name("performance")
+                                  .long("performance")
+                                  .help("Prints the wins each team was expected to take against the wins it took to stderr"))
+                              .arg(Arg::with_name("summary")
+                                  .long("summary")
+                                  .help("Prints a summary of the run to stderr"))
+                              .arg(Arg::with_name("round")
+                                  .long("round")
+                                  .value_name("N")
+                                  .help("Rounds the ratings in the output standings to N decimal places")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("normalize")
+                                  .long("normalize")
+                                  .value_name("MEAN")
+                                  .help("Shifts every rating after the run by the same amount so the mean rating is MEAN")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("reset-ratings")
+                                  .long("reset-ratings")
+                                  .help("Starts every team in the standings from default_rating with no games played, so only the team names are kept"))
+                              .arg(Arg::with_name("division")
+                                  .long("division")
+                                  .value_name("NAME")
+                                  .help("Only writes the teams of division NAME to the output standings and leaderboard, matches against other divisions still count")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("top")
+                                  .long("top")
+                                  .value_name("N")
+                                  .help("Only writes the N highest rated teams to the output standings and leaderboard, every team is still rated")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("with-uncertainty")
+                                  .long("with-uncertainty")
+                                  .help("Writes the games played and rating deviation of every team alongside its rating"))
+                              .arg(Arg::with_name("with-previous")
+                                  .long("with-previous")
+                                  .help("Writes every team as its old rating, new rating and the change between them")
+                                  .conflicts_with("with-uncertainty"))
+                              .arg(Arg::with_name("validate-only")
+                                  .long("validate-only")
+                                  .help("Checks the config, standings and matches files against their schemas and reports every problem without applying any matches"))
+                              .arg(Arg::with_name("check")
+                                  .long("check")
+                                  .help("Checks that every match names known teams, a valid series kind and two different teams, and prints a report without applying any matches"))
+                              .arg(Arg::with_name("dry-run")
+                                  .long("dry-run")
+                                  .help("Prints the new standings to stdout instead of writing the output file"))
+                              .arg(Arg::with_name("output-format")
+                                  .long("output-format")
+                                  .value_name("FORMAT")
+                                  .help("Format of the output standings")
+                                  .takes_value(true)
+                                  .possible_values(&["json", "csv"])
+                                  .default_value("json"))
+                              .arg(Arg::with_name("leaderboard")
+                                  .long("leaderboard")
+                                  .help("Prints the new standings as a ranked table"))
+                              .arg(Arg::with_name("history")
+                                  .long("history")
+                                  .value_name("FILE")
+                                  .help("Writes the standings after every match to FILE")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("snapshot-every")
+                                  .long("snapshot-every")
+                                  .value_name("N")
+                                  .help("Writes the standings to the snapshot directory after every N matches")
+                                  .takes_value(true)
+                                  .requires("snapshot-dir"))
+                              .arg(Arg::with_name("snapshot-dir")
+                                  .long("snapshot-dir")
+                                  .value_name("DIR")
+                                  .help("Directory snapshots are written to")
+                                  .takes_value(true)
+                                  .requires("snapshot-every"))
+                              .arg(Arg::with_name("report")
+                                  .long("report")
+                                  .value_name("FILE")
+                                  .help("Writes a JSON report of the config, matches, rating changes and warnings of the run to FILE")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("compare")
+                                  .long("compare")
+                                  .value_name("REF_FILE")
+                                  .help("Fails if any team's new rating differs from the reference standings in REF_FILE by more than the tolerance")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("tolerance")
+                                  .long("tolerance")
+                                  .value_name("T")
+                                  .help("Largest rating difference --compare accepts, defaults to 0")
+                                  .takes_value(true)
+                                  .requires("compare"))
+                              .arg(Arg::with_name("team")
+                                  .long("team")
+                                  .value_name("NAME")
+                                  .help("Prints every match NAME played with its rating before and after, then its final rating and rank")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("games")
+                                  .long("games")
+                                  .value_name("FILE")
+                                  .help("Writes the number of games each team played to FILE")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("movers")
+                                  .long("movers")
+                                  .value_name("N")
+                                  .help("Prints the N teams that gained and lost the most rating to stderr")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("strict")
+                                  .long("strict")
+                                  .value_name("LEVEL")
+                                  .help("What to do when match teams and standings teams do not line up, a team plays itself or the k brackets do not start at 0: off carries on, warn prints a warning and error fails, warn by default and error when given without a level")
+                                  .takes_value(true)
+                                  .min_values(0)
+                                  .max_values(1)
+                                  .possible_values(&["off", "warn", "error"]))
+                              .arg(Arg::with_name("log")
+                                  .short("l")
+                                  .long("log")
+                                  .value_name("FILE")
+                                  .help("Log every rating change to stderr, or to FILE if given")
+                                  .takes_value(true)
+                                  .min_values(0)))
                           .subcommand(SubCommand::with_name("show")
-                              .about("Shows probabilities based on standings")
+                              .alias("predict")
+                              .about("Shows win probabilities between two teams based on standings")
+                              .arg(Arg::with_name("config")
+                                  .short("c")
+                                  .long("config")
+                                  .value_name("FILE")
+                                  .help("Path to a JSON or TOML config file to take elo_base and elo_divisor from, default is the path in ELO_CONFIG if set, repeat to layer override files over it")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1))
                               .arg(Arg::with_name("teamA")
                                   .help("Name of first team")
                                   .takes_value(true)
-                                  .required(true))
+                                  .required_unless("matrix"))
                               .arg(Arg::with_name("teamB")
                                   .help("Name of second team")
                                   .takes_value(true)
+                                  .required_unless("matrix"))
+                              .arg(Arg::with_name("matrix")
+                                  .long("matrix")
+                                  .help("Prints a JSON object where matrix[a][b] is the probability team a beats team b, for every pair of teams")
+                                  .conflicts_with_all(&["teamA", "teamB"]))
+                              .arg(Arg::with_name("standings")
+                                  .short("s")
+                                  .long("standings")
+                                  .value_name("FILE")
+                                  .help("Path to standings file, `-` reads from stdin")
+                                  .takes_value(true)
+                                  .required(true)))
+                          .subcommand(SubCommand::with_name("decay")
+                              .about("Moves the ratings of inactive teams toward the mean rating")
+                              .arg(Arg::with_name("standings")
+                                  .short("s")
+                                  .long("standings")
+                                  .value_name("FILE")
+                                  .help("Path to standings file, `-` reads from stdin")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("output")
+                                  .short("o")
+                                  .long("output")
+                                  .value_name("FILE")
+                                  .help("Path to output standings")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("factor")
+                                  .short("f")
+                                  .long("factor")
+                                  .value_name("FACTOR")
+                                  .help("Fraction of the distance to the mean each inactive team moves, between 0 and 1")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("active")
+                                  .short("a")
+                                  .long("active")
+                                  .value_name("TEAM")
+                                  .help("Team that has been active and should not decay. Can be repeated or comma separated")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1)))
+                          .subcommand(SubCommand::with_name("simulate")
+                              .about("Simulates the rest of a season and reports finish position probabilities")
+                              .arg(Arg::with_name("config")
+                                  .short("c")
+                                  .long("config")
+                                  .value_name("FILE")
+                                  .help("Path to a JSON or TOML config file, default is the path in ELO_CONFIG or else `config.json`, repeat to layer override files over it")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1))
+                              .arg(Arg::with_name("standings")
+                                  .short("s")
+                                  .long("standings")
+                                  .value_name("FILE")
+                                  .help("Path to standings file, `-` reads from stdin")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("schedule")
+                                  .long("schedule")
+                                  .value_name("FILE")
+                                  .help("Path to a JSON list of upcoming matchups")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("iterations")
+                                  .short("n")
+                                  .long("iterations")
+                                  .value_name("N")
+                                  .help("Number of seasons to simulate")
+                                  .takes_value(true)
+                                  .default_value("10000"))
+                              .arg(Arg::with_name("seed")
+                                  .long("seed")
+                                  .value_name("SEED")
+                                  .help("Seed for the random number generator")
+                                  .takes_value(true)
+                                  .default_value("0"))
+                              .arg(Arg::with_name("output")
+                                  .short("o")
+                                  .long("output")
+                                  .value_name("FILE")
+                                  .help("Path to write the probabilities to, printed to stdout by default")
+                                  .takes_value(true)))
+                          .subcommand(SubCommand::with_name("undo")
+                              .about("Reverses the rating change a single match caused")
+                              .arg(Arg::with_name("config")
+                                  .short("c")
+                                  .long("config")
+                                  .value_name("FILE")
+                                  .help("Path to a JSON or TOML config file, default is the path in ELO_CONFIG or else `config.json`, repeat to layer override files over it")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1))
+                              .arg(Arg::with_name("standings")
+                                  .short("s")
+                                  .long("standings")
+                                  .value_name("FILE")
+                                  .help("Path to standings file, `-` reads from stdin")
+                                  .takes_value(true)
                                   .required(true))
+                              .arg(Arg::with_name("match")
+                                  .short("m")
+                                  .long("match")
+                                  .value_name("FILE")
+                                  .help("Path to a file containing the single match to undo")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("output")
+                                  .short("o")
+                                  .long("output")
+                                  .value_name("FILE")
+                                  .help("Path to output standings")
+                                  .takes_value(true)
+                                  .required(true)))
+                          .subcommand(SubCommand::with_name("init")
+                              .about("Creates standings with every team at the default rating")
+                              .arg(Arg::with_name("config")
+                                  .short("c")
+                                  .long("config")
+                                  .value_name("FILE")
+                                  .help("Path to a JSON or TOML config file to take default_rating from, default is the path in ELO_CONFIG if set and 1500 is used otherwise, repeat to layer override files over it")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1))
+                              .arg(Arg::with_name("teams")
+                                  .short("t")
+                                  .long("teams")
+                                  .value_name("FILE")
+                                  .help("Path to a file with one team name per line or a JSON array of names, `-` reads from stdin")
+                                  .takes_value(true)
+                                  .required_unless("seed-ratings-from-winrate")
+                                  .conflicts_with("seed-ratings-from-winrate"))
+                              .arg(Arg::with_name("seed-ratings-from-winrate")
+                                  .long("seed-ratings-from-winrate")
+                                  .value_name("FILE")
+                                  .help("Path to a JSON object from team name to [wins, losses], teams are seeded around the default rating by win rate")
+                                  .takes_value(true))
+                              .arg(Arg::with_name("winrate-scale")
+                                  .long("winrate-scale")
+                                  .value_name("RATING")
+                                  .help("Rating difference between a team that won every game and one that lost every game, 400 by default")
+                                  .takes_value(true)
+                                  .requires("seed-ratings-from-winrate"))
+                              .arg(Arg::with_name("output")
+                                  .short("o")
+                                  .long("output")
+                                  .value_name("FILE")
+                                  .help("Path to output standings")
+                                  .takes_value(true)
+                                  .required(true)))
+                          .subcommand(SubCommand::with_name("repl")
+                              .about("Reads results from stdin one line at a time, such as `C9 TSM bo3`, and updates the standings as they are entered")
+                              .arg(Arg::with_name("config")
+                                  .short("c")
+                                  .long("config")
+                                  .value_name("FILE")
+                                  .help("Path to a JSON or TOML config file, the path in ELO_CONFIG or else config.json is used by default, repeat to layer override files over it")
+                                  .takes_value(true)
+                                  .multiple(true)
+                                  .number_of_values(1))
                               .arg(Arg::with_name("standings")
                                   .short("s")
                                   .long("standings")
                                   .value_name("FILE")
-                                  .help("Path to standings file")
+                                  .help("Path to standings")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("output")
+                                  .short("o")
+                                  .long("output")
+                                  .value_name("FILE")
+                                  .help("Path the standings are written to on :save and when the session ends")
+                                  .takes_value(true)
+                                  .required(true)))
+                          .subcommand(SubCommand::with_name("merge")
+                              .about("Combines two standings files, such as those of two divisions that share teams")
+                              .arg(Arg::with_name("first")
+                                  .help("Path to the first standings file")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("second")
+                                  .help("Path to the second standings file")
+                                  .takes_value(true)
+                                  .required(true))
+                              .arg(Arg::with_name("strategy")
+                                  .long("strategy")
+                                  .value_name("STRATEGY")
+                                  .help("How the ratings of teams in both files are combined")
+                                  .takes_value(true)
+                                  .possible_values(&["average", "max", "weighted-by-games"])
+                                  .default_value("average"))
+                              .arg(Arg::with_name("with-uncertainty")
+                                  .long("with-uncertainty")
+                                  .help("Writes every team with its combined games played and rating deviation, keeping display names"))
+                              .arg(Arg::with_name("output")
+                                  .short("o")
+                                  .long("output")
+                                  .value_name("FILE")
+                                  .help("Path to output standings, `-` writes to stdout")
                                   .takes_value(true)
                                   .required(true))).get_matches();
                             
@@ -218,49 +515,625 @@ fn main() {
     match matches.subcommand() {
         ("update", Some(sub_m)) => {
             let standings_path = sub_m.value_of("standings").unwrap();
-            let matches_path = sub_m.value_of("matches").unwrap();
-            let output_path = sub_m.value_of("output").unwrap();
-            let config_path = sub_m.value_of("config").unwrap_or("config.json");
+            let matches_paths: Vec<&str> = sub_m.values_of("matches").into_iter()
+                .flatten()
+                .flat_map(|paths| paths.split(','))
+                .collect();
+            let output_path = sub_m.value_of("output");
+            let config_paths = config_paths_for(sub_m).unwrap_or_else(|| vec![PathBuf::from("config.json")]);
+            let config_paths: Vec<&Path> = config_paths.iter().map(PathBuf::as_path).collect();
+
+            let stdin_count = matches_paths.iter().chain([standings_path].iter()).filter(|path| **path == "-").count();
+            if stdin_count > 1 {
+                return Err("only one of the standings and matches files can be read from stdin".into());
+            }
+
+            if let Some(teams) = sub_m.values_of("predict") {
+                let teams: Vec<&str> = teams.collect();
+                let standings = parse_standings_from_path(Path::new(standings_path))
+                    .map_err(|error| format!("could not read standings file: {}", error))?;
+                // Only a config given explicitly is read, the default `config.json` is not needed to predict.
+                let config = match config_paths_for(sub_m) {
+                    Some(config_paths) => Some(parse_layered_config(&config_paths.iter().map(PathBuf::as_path).collect::<Vec<_>>())
+                        .map_err(|error| format!("could not read config file: {}", error))?),
+                    None => None,
+                };
+                return print_prediction(&standings, config.as_ref(), teams[0], teams[1]);
+            }
+
+            if sub_m.is_present("validate-only") {
+                // Layered config files are only complete once merged, so they are checked together.
+                let config_problems = match parse_layered_config_value(&config_paths) {
+                    Ok(value) => validate_value(InputKind::Configuration, &value),
+                    Err(error) => vec![error.to_string()],
+                };
+                let config_label: Vec<String> = config_paths.iter().map(|path| path.display().to_string()).collect();
+                let mut problem_count = 0;
+                for problem in config_problems {
+                    eprintln!("{}: {}", config_label.join(" + "), problem);
+                    problem_count += 1;
+                }
+                let inputs = iter::once((InputKind::Standings, standings_path))
+                    .chain(matches_paths.iter().map(|matches_path| (InputKind::Matches, *matches_path)));
+                for (kind, path) in inputs {
+                    for problem in validate_file(kind, Path::new(path)) {
+                        eprintln!("{}: {}", path, problem);
+                        problem_count += 1;
+                    }
+                }
+                if problem_count > 0 {
+                    return Err(format!("found {} problems in the input files", problem_count).into());
+                }
+                println!("input files are valid");
+                return Ok(());
+            }
+
+            if sub_m.is_present("check") {
+                let config = parse_layered_config(&config_paths)
+                    .map_err(|error| format!("could not read config file: {}", error))?;
+                config.validate()?;
+                let standings = parse_standings_file_from_path(Path::new(standings_path))
+                    .map_err(|error| format!("could not read standings file: {}", error))?
+                    .standings;
+                // Read in the same order and formats as the matches `update` would apply.
+                let mut matches = Vec::new();
+                if let (Some(winner), Some(loser)) = (sub_m.value_of("match-winner"), sub_m.value_of("match-loser")) {
+                    let series = sub_m.value_of("match-series").unwrap_or("bo1").parse()?;
+                    matches.push(UncheckedMatch::from(&MatchResult { winner: winner.to_string(), loser: loser.to_string(), series, ..Default::default() }));
+                }
+                let ndjson = sub_m.value_of("matches-format") == Some("ndjson");
+                for matches_path in matches_paths.iter() {
+                    let file_matches = if ndjson {
+                        parse_unchecked_ndjson_matches_from_path(Path::new(matches_path))
+                    } else {
+                        parse_unchecked_matches_from_path(Path::new(matches_path))
+                    };
+                    matches.extend(file_matches.map_err(|error| format!("could not read matches file: {}", error))?);
+                }
+                let check = check_matches(&matches, &standings, &config);
+                print!("{}", check);
+                if !check.passed() {
+                    return Err(format!("found {} problems in the matches", check.problem_count()).into());
+                }
+                return Ok(());
+            }
+
+            let StandingsFile { standings, games_played: previous_games_played, display_names, divisions } = parse_standings_file_from_path(Path::new(standings_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+
+            let mut matches = Vec::new();
+            if let (Some(winner), Some(loser)) = (sub_m.value_of("match-winner"), sub_m.value_of("match-loser")) {
+                let series = sub_m.value_of("match-series").unwrap_or("bo1").parse()?;
+                matches.push(MatchResult { winner: winner.to_string(), loser: loser.to_string(), series, ..Default::default() });
+            }
+            let ndjson = sub_m.value_of("matches-format") == Some("ndjson");
+            let group_games = sub_m.is_present("group-games");
+            let stream_matches = !matches_paths.is_empty()
+                && !group_games
+                && matches_paths.iter().all(|path| ndjson || is_ndjson(Path::new(path)))
+                && !NEEDS_ALL_MATCHES.iter().any(|option| sub_m.is_present(option));
+            if group_games {
+                let mut games = Vec::new();
+                for matches_path in matches_paths.iter() {
+                    games.extend(parse_game_results_from_path(Path::new(matches_path))
+                        .map_err(|error| format!("could not read games file: {}", error))?);
+                }
+                matches.extend(group_games_into_series(&games)?);
+            } else if !stream_matches {
+                for matches_path in matches_paths.iter() {
+                    let file_matches = if ndjson {
+                        open_ndjson_matches(Path::new(matches_path)).and_then(Iterator::collect)
+                    } else {
+                        parse_match_results_from_path(Path::new(matches_path))
+                    };
+                    matches.extend(file_matches.map_err(|error| format!("could not read matches file: {}", error))?);
+                }
+            }
+            let flips = sub_m.values_of("flip")
+                .into_iter()
+                .flatten()
+                .flat_map(|indices| indices.split(','))
+                .map(|index| index.trim().parse::<usize>().map_err(|_| format!("invalid match index '{}'", index)))
+                .collect::<Result<Vec<usize>, String>>()?;
+            flip_results(&mut matches, &flips)?;
+            filter_by_date(&mut matches, sub_m.value_of("since"), sub_m.value_of("until"), sub_m.is_present("include-undated"))?;
+            if sub_m.is_present("sort-by-date") {
+                sort_by_date(&mut matches);
+            }
+
+            let config = parse_layered_config(&config_paths)
+                .map_err(|error| format!("could not read config file: {}", error))?;
+            config.validate()?;
+            let (standings, previous_games_played) = if sub_m.is_present("reset-ratings") {
+                let teams: Vec<String> = standings.keys().cloned().collect();
+                (standings_from_team_list(&teams, config.default_rating()), GamesPlayed::new())
+            } else {
+                (standings, previous_games_played)
+            };
+            // Dropped up front so matches against placeholder teams stay out of every report too.
+            matches.retain(|result| !config.involves_ignored_team(result));
+
+            let strictness = match (sub_m.is_present("strict"), sub_m.value_of("strict")) {
+                (_, Some(level)) => level.parse()?,
+                (true, None) => Strictness::Error,
+                (false, None) => Strictness::Warn,
+            };
+            let mut warnings = config.warnings();
+            let is_self_match = |result: &MatchResult| config.counts_match(result)
+                && config.canonical_team(&result.winner) == config.canonical_team(&result.loser);
+            // Streamed matches are not known up front, they are checked as they are read instead.
+            if !stream_matches {
+                for (index, result) in matches.iter().enumerate().filter(|(_, result)| is_self_match(result)) {
+                    warnings.push(format!("skipped match {} ({}), a team cannot play against itself", index, result));
+                }
+                matches.retain(|result| !is_self_match(result));
+                warnings.extend(cross_reference_canonical_teams(&matches, &standings, &config).warnings());
+            }
+            for warning in strictness.check(&warnings)? {
+                eprintln!("warning: {}", warning);
+            }
+            // Weighted once the matches that are never rated are gone, so they do not age the rest.
+            if let Some(half_life) = sub_m.value_of("half-life") {
+                match half_life.parse::<f32>() {
+                    Ok(half_life) if half_life > 0f32 && half_life.is_finite() => weight_by_recency(&mut matches, half_life),
+                    _ => return Err(format!("invalid half-life '{}', expected a positive number", half_life).into()),
+                }
+            }
+
+            if sub_m.is_present("tune") {
+                let range = sub_m.value_of("tune").unwrap_or("1,100");
+                let bounds: Vec<Option<f32>> = range.split(',').map(|bound| bound.trim().parse().ok()).collect();
+                let (min_k, max_k) = match bounds[..] {
+                    [Some(min_k), Some(max_k)] => (min_k, max_k),
+                    _ => return Err(format!("invalid --tune range '{}', expected MIN,MAX", range).into()),
+                };
+                let tuning = tune_k(&matches, &standings, &previous_games_played, &config, min_k, max_k)
+                    .map_err(|error| format!("could not tune k: {}", error))?;
+                print!("{}", tuning);
+                return Ok(());
+            }
 
-            let standings = match parse_standings_from_path(Path::new(standings_path)) {
-                Ok(v) => v,
-                Err(error) => panic!("Problem reading standings: {:?}", error),
+            let tolerance = match sub_m.value_of("tolerance") {
+                Some(tolerance) => match tolerance.parse::<f32>() {
+                    Ok(tolerance) if tolerance >= 0f32 => tolerance,
+                    _ => return Err(format!("invalid tolerance '{}', expected a non-negative number", tolerance).into()),
+                },
+                None => 0f32,
+            };
+            let reference = match sub_m.value_of("compare") {
+                Some(reference_path) => Some(parse_standings_from_path(Path::new(reference_path))
+                    .map_err(|error| format!("could not read reference standings file: {}", error))?),
+                None => None,
             };
 
-            let matches = match parse_match_results_from_path(Path::new(matches_path)) {
-                Ok(v) => v,
-                Err(error) => panic!("Problem reading match results: {:?}", error),
+            let mut log: Option<Box<dyn Write>> = match (sub_m.is_present("log"), sub_m.value_of("log")) {
+                (true, Some(log_path)) => Some(Box::new(File::create(log_path)
+                    .map_err(|error| format!("could not create log file '{}': {}", log_path, error))?)),
+                (true, None) => Some(Box::new(io::stderr())),
+                (false, _) => None,
+            };
+            let history_path = sub_m.value_of("history");
+            let mut history = Vec::new();
+            let mut games_played = previous_games_played.clone();
+            let mut wins = Wins::new();
+            let mut calibration = Calibration::default();
+            let mut performance = Performance::default();
+            let report_team = sub_m.value_of("team").map(|team| config.canonical_team(team).to_string());
+            let mut team_matches = Vec::new();
+            let snapshot_every = match sub_m.value_of("snapshot-every") {
+                Some(every) => match every.parse::<usize>() {
+                    Ok(every) if every > 0 => Some(every),
+                    _ => return Err(format!("invalid snapshot interval '{}', expected a positive number of matches", every).into()),
+                },
+                None => None,
             };
+            let snapshot_dir = sub_m.value_of("snapshot-dir").map(Path::new);
+            if let Some(snapshot_dir) = snapshot_dir {
+                fs::create_dir_all(snapshot_dir)
+                    .map_err(|error| format!("could not create snapshot directory '{}': {}", snapshot_dir.display(), error))?;
+            }
+            let mut matches_applied = 0;
 
-            let config = match parse_type_from_path::<Configuration>(Path::new(config_path)) {
-                Ok(v) => v,
-                Err(error) => panic!("Problem reading config results: {:?}", error),
+            let mut record_update = |update: &MatchUpdate| -> Result<(), Box<dyn Error>> {
+                if let Some(log) = log.as_mut() {
+                    writeln!(log, "{}", update)?;
+                }
+                update.record_games_played(&mut games_played);
+                update.record_win(&mut wins);
+                calibration.record(update);
+                performance.record(update);
+                if let Some(team) = report_team.as_ref() {
+                    team_matches.extend(update.for_team(team));
+                }
+                Ok(())
+            };
+            let mut record_standings = |new_standings: &Standings| -> Result<(), Box<dyn Error>> {
+                if history_path.is_some() {
+                    history.push(new_standings.clone());
+                }
+                matches_applied += 1;
+                if let (Some(every), Some(snapshot_dir)) = (snapshot_every, snapshot_dir) {
+                    if matches_applied % every == 0 {
+                        let snapshot_path = snapshot_dir.join(format!("standings_{:04}.json", matches_applied));
+                        write_standings_to_path(&snapshot_path, new_standings)
+                            .map_err(|error| format!("could not write snapshot '{}': {}", snapshot_path.display(), error))?;
+                    }
+                }
+                Ok(())
+            };
+            let needs_standings = history_path.is_some() || snapshot_every.is_some();
+            if sub_m.is_present("explain") {
+                if let Some(result) = matches.first() {
+                    let explanation = explain_match(result, &standings, &previous_games_played, &config)
+                        .map_err(|error| format!("could not apply match results: {}", error))?;
+                    eprint!("{}", explanation);
+                }
+            }
+            let mut streamed_teams = HashSet::new();
+            let new_standings = if stream_matches {
+                let streamed = matches_paths.iter().flat_map(|path| match open_ndjson_matches(Path::new(path)) {
+                    Ok(matches) => Box::new(matches) as Box<dyn Iterator<Item = Result<MatchResult, EloError>>>,
+                    Err(error) => Box::new(iter::once(Err(error))),
+                })
+                    .filter(|result| !matches!(result, Ok(result) if config.involves_ignored_team(result)))
+                    .enumerate()
+                    .filter_map(|(index, result)| match result {
+                        // Skipped as it is read, the same as a self-match in a file read in full.
+                        Ok(result) if is_self_match(&result) => {
+                            let warning = [format!("skipped match {} ({}), a team cannot play against itself", index, result)];
+                            match strictness.check(&warning) {
+                                Ok(warnings) => {
+                                    for warning in warnings {
+                                        eprintln!("warning: {}", warning);
+                                    }
+                                    None
+                                },
+                                Err(_) => Some(Err(EloError::SelfMatch(config.canonical_team(&result.winner).to_string()))),
+                            }
+                        },
+                        Ok(result) => {
+                            streamed_teams.insert(config.canonical_team(&result.winner).to_string());
+                            streamed_teams.insert(config.canonical_team(&result.loser).to_string());
+                            Some(Ok(result))
+                        },
+                        Err(error) => Some(Err(error)),
+                    });
+                apply_match_stream(streamed, &standings, &previous_games_played, &config, |update, new_standings| {
+                    record_update(update)?;
+                    record_standings(new_standings)
+                })
+            } else if needs_standings {
+                apply_match_results_inspect_with_games(&matches, &standings, &previous_games_played, &config, |update, new_standings| {
+                    record_update(update)?;
+                    record_standings(new_standings)
+                })
+            } else {
+                // Nothing needs the standings in between matches, so the interned ids are used.
+                apply_match_results_inspect_updates(&matches, &standings, &previous_games_played, &config, &mut record_update)
+            }
+                .map_err(|error| format!("could not apply match results: {}", error))?;
+            if stream_matches {
+                let cross_reference = cross_reference_team_names(streamed_teams.iter().map(String::as_str), &standings);
+                for warning in strictness.check(&cross_reference.warnings())? {
+                    eprintln!("warning: {}", warning);
+                }
+            }
+            let new_standings = match sub_m.value_of("normalize") {
+                Some(mean) => match mean.parse::<f32>() {
+                    Ok(mean) if mean.is_finite() => normalize_standings(&new_standings, mean),
+                    _ => return Err(format!("invalid --normalize mean '{}', expected a number", mean).into()),
+                },
+                None => new_standings,
             };
 
-            let series_win_weight = get_series_win_weight_from_config(config.clone());
+            // Checked before anything is written so a misspelt team leaves no output behind.
+            let team_report = match report_team {
+                Some(team) => Some(format_team_report(&team, &team_matches, &new_standings, &games_played, &display_names)?),
+                None => None,
+            };
 
-            match write_standings_to_path(Path::new(output_path), &apply_match_results(&matches, &standings, &config.k_brackets, &series_win_weight).unwrap()) {
-                Ok(v) => v,
-                Err(error) => panic!("Problem writing standings: {:?}", error)
+            let output_format = sub_m.value_of("output-format");
+            let with_uncertainty = sub_m.is_present("with-uncertainty");
+            if with_uncertainty && output_format == Some("csv") {
+                return Err("--with-uncertainty is only supported for JSON output".into());
+            }
+            let with_previous = sub_m.is_present("with-previous");
+            if with_previous && output_format == Some("csv") {
+                return Err("--with-previous is only supported for JSON output".into());
+            }
+
+            let division_standings = match sub_m.value_of("division") {
+                Some(division) => filter_by_division(&new_standings, &divisions, division),
+                None => new_standings.clone(),
+            };
+            let shown_standings = match sub_m.value_of("top") {
+                Some(count) => match count.parse::<usize>() {
+                    Ok(count) if count > 0 => top_standings(&division_standings, &games_played, count),
+                    _ => return Err(format!("invalid --top count '{}', expected a positive number of teams", count).into()),
+                },
+                None => division_standings,
+            };
+            let round_places = match sub_m.value_of("round") {
+                Some(places) => Some(places.parse::<u32>().map_err(|error| format!("invalid number of decimal places: {}", error))?),
+                None => None,
+            };
+            let output_standings = match round_places {
+                Some(places) => round_standings(&shown_standings, places),
+                None => shown_standings.clone(),
             };
+            let rating_changes = || {
+                let changes = standings_with_previous(&standings, &shown_standings, &config);
+                match round_places {
+                    Some(places) => changes.into_iter().map(|(team, change)| (team, change.rounded(places))).collect(),
+                    None => changes,
+                }
+            };
+
+            match output_path {
+                Some(output_path) if !sub_m.is_present("dry-run") => {
+                    let write_result = match output_format {
+                        Some("csv") => write_standings_csv(Path::new(output_path), &output_standings),
+                        _ if with_uncertainty => write_type_to_path(Path::new(output_path), &standings_with_uncertainty(&output_standings, &games_played, &display_names, &divisions)),
+                        _ if with_previous => write_type_to_path(Path::new(output_path), &rating_changes()),
+                        _ => write_standings_to_path(Path::new(output_path), &output_standings),
+                    };
+                    write_result
+                        .map_err(|error| format!("could not write standings file '{}': {}", output_path, error))?;
+                },
+                _ => match output_format {
+                    Some("csv") => write_standings_csv_to_writer(io::stdout(), &output_standings)?,
+                    _ if with_uncertainty => println!("{}", to_sorted_json_string(&standings_with_uncertainty(&output_standings, &games_played, &display_names, &divisions))?),
+                    _ if with_previous => println!("{}", to_sorted_json_string(&rating_changes())?),
+                    _ => println!("{}", to_sorted_json_string(&output_standings)?),
+                },
+            }
+
+            if let Some(history_path) = history_path {
+                write_type_to_path(Path::new(history_path), &history)
+                    .map_err(|error| format!("could not write history file '{}': {}", history_path, error))?;
+            }
+
+            if let Some(report_path) = sub_m.value_of("report") {
+                let report = build_run_report(&config, &standings, &new_standings, &matches, &games_played, &wins, warnings)
+                    .with_display_names(&display_names);
+                write_type_to_path(Path::new(report_path), &report)
+                    .map_err(|error| format!("could not write report file '{}': {}", report_path, error))?;
+            }
+
+            if let Some(games_path) = sub_m.value_of("games") {
+                write_type_to_path(Path::new(games_path), &games_played)
+                    .map_err(|error| format!("could not write games file '{}': {}", games_path, error))?;
+            }
+
+            if let Some(count) = sub_m.value_of("movers") {
+                let count: usize = count.parse().map_err(|error| format!("invalid movers count: {}", error))?;
+                eprint!("{}", format_movers(&standings, &new_standings, config.default_rating(), count));
+            }
+
+            if sub_m.is_present("summary") {
+                eprint!("{}", summarize_run(&standings, &new_standings, &matches, &config));
+            }
+
+            if sub_m.is_present("calibrate") {
+                eprint!("{}", calibration);
+            }
+
+            if sub_m.is_present("performance") {
+                eprint!("{}", performance);
+            }
+
+            if sub_m.is_present("leaderboard") {
+                // Keep stdout to just the standings when they are written there.
+                if output_path == Some("-") {
+                    eprint!("{}", format_leaderboard_with_names(&shown_standings, &games_played, &display_names, config.leaderboard_min_games));
+                } else {
+                    print!("{}", format_leaderboard_with_names(&shown_standings, &games_played, &display_names, config.leaderboard_min_games));
+                }
+            }
+
+            if let Some(team_report) = team_report {
+                if output_path == Some("-") {
+                    eprint!("{}", team_report);
+                } else {
+                    print!("{}", team_report);
+                }
+            }
+
+            if let Some(reference) = reference {
+                let mismatches = compare_standings(&output_standings, &reference, tolerance);
+                for mismatch in mismatches.iter() {
+                    eprintln!("{}", mismatch);
+                }
+                if !mismatches.is_empty() {
+                    return Err(format!("{} teams differ from the reference standings by more than {}", mismatches.len(), tolerance).into());
+                }
+            }
         },
         ("show", Some(sub_m)) => {
             let standings_path = sub_m.value_of("standings").unwrap();
 
-            let team_a = sub_m.value_of("teamA").unwrap();
-            let team_b = sub_m.value_of("teamB").unwrap();
+            let standings = parse_standings_from_path(Path::new(standings_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+            let config = match config_paths_for(sub_m) {
+                Some(config_paths) => Some(parse_layered_config(&config_paths.iter().map(PathBuf::as_path).collect::<Vec<_>>())
+                    .map_err(|error| format!("could not read config file: {}", error))?),
+                None => None,
+            };
+
+            if sub_m.is_present("matrix") {
+                let scale = config.as_ref().map(Configuration::scale).unwrap_or_default();
+                println!("{}", to_sorted_json_string(&probability_matrix(&standings, scale))?);
+                return Ok(());
+            }
+
+            print_prediction(&standings, config.as_ref(), sub_m.value_of("teamA").unwrap(), sub_m.value_of("teamB").unwrap())?;
+        },
+        ("decay", Some(sub_m)) => {
+            let standings_path = sub_m.value_of("standings").unwrap();
+            let output_path = sub_m.value_of("output").unwrap();
+
+            let factor: f32 = sub_m.value_of("factor").unwrap().parse()
+                .map_err(|error| format!("invalid decay factor: {}", error))?;
+            if !(0f32..=1f32).contains(&factor) {
+                return Err(format!("decay factor must be between 0 and 1, got {}", factor).into());
+            }
+
+            let active_teams: HashSet<String> = sub_m.values_of("active")
+                .into_iter()
+                .flatten()
+                .flat_map(|teams| teams.split(','))
+                .map(|team| team.to_string())
+                .collect();
+
+            let standings = parse_standings_from_path(Path::new(standings_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+
+            write_standings_to_path(Path::new(output_path), &decay_standings(&standings, factor, &active_teams))
+                .map_err(|error| format!("could not write standings file '{}': {}", output_path, error))?;
+        },
+        ("simulate", Some(sub_m)) => {
+            let standings_path = sub_m.value_of("standings").unwrap();
+            let schedule_path = sub_m.value_of("schedule").unwrap();
+            let config_paths = config_paths_for(sub_m).unwrap_or_else(|| vec![PathBuf::from("config.json")]);
+            let config_paths: Vec<&Path> = config_paths.iter().map(PathBuf::as_path).collect();
 
-            let standings = match parse_standings_from_path(Path::new(standings_path)) {
-                Ok(v) => v,
-                Err(error) => panic!("Problem reading standings: {:?}", error),
+            let iterations = match sub_m.value_of("iterations").unwrap().parse::<u32>() {
+                Ok(iterations) if iterations > 0 => iterations,
+                _ => return Err(format!("invalid iteration count '{}', expected a positive number", sub_m.value_of("iterations").unwrap()).into()),
             };
+            let seed: u64 = sub_m.value_of("seed").unwrap().parse()
+                .map_err(|error| format!("invalid seed: {}", error))?;
 
-            match get_probabilities_from_standings(&standings, team_a, team_b) {
-                Some(v) => println!("{:?}", v),
-                None => println!("Unrecognized team name")
+            let standings = parse_standings_from_path(Path::new(standings_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+
+            let schedule = parse_type_from_path::<Vec<Matchup>>(Path::new(schedule_path))
+                .map_err(|error| format!("could not read schedule file: {}", error))?;
+
+            let config = parse_layered_config(&config_paths)
+                .map_err(|error| format!("could not read config file: {}", error))?;
+            config.validate()?;
+
+            let probabilities = simulate_season(&standings, &schedule, &config, iterations, seed)
+                .map_err(|error| format!("could not simulate the season: {}", error))?;
+
+            match sub_m.value_of("output") {
+                Some(output_path) => write_type_to_path(Path::new(output_path), &probabilities)
+                    .map_err(|error| format!("could not write probabilities file '{}': {}", output_path, error))?,
+                None => println!("{}", to_sorted_json_string(&probabilities)?),
+            }
+        },
+        ("undo", Some(sub_m)) => {
+            let standings_path = sub_m.value_of("standings").unwrap();
+            let match_path = sub_m.value_of("match").unwrap();
+            let output_path = sub_m.value_of("output").unwrap();
+            let config_paths = config_paths_for(sub_m).unwrap_or_else(|| vec![PathBuf::from("config.json")]);
+            let config_paths: Vec<&Path> = config_paths.iter().map(PathBuf::as_path).collect();
+
+            let standings = parse_standings_from_path(Path::new(standings_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+
+            let result = parse_type_from_path::<MatchResult>(Path::new(match_path))
+                .map_err(|error| format!("could not read match file: {}", error))?;
+
+            let config = parse_layered_config(&config_paths)
+                .map_err(|error| format!("could not read config file: {}", error))?;
+            config.validate()?;
+
+            let new_standings = undo_match_result(&result, &standings, &config)
+                .map_err(|error| format!("could not undo match: {}", error))?;
+
+            write_standings_to_path(Path::new(output_path), &new_standings)
+                .map_err(|error| format!("could not write standings file '{}': {}", output_path, error))?;
+        },
+        ("init", Some(sub_m)) => {
+            let output_path = sub_m.value_of("output").unwrap();
+
+            let default_rating = match config_paths_for(sub_m) {
+                Some(config_paths) => parse_layered_config(&config_paths.iter().map(PathBuf::as_path).collect::<Vec<_>>())
+                    .map_err(|error| format!("could not read config file: {}", error))?
+                    .default_rating(),
+                None => DEFAULT_RATING,
+            };
+
+            let standings = if let Some(records_path) = sub_m.value_of("seed-ratings-from-winrate") {
+                let records = parse_type_from_path::<HashMap<String, WinLossRecord>>(Path::new(records_path))
+                    .map_err(|error| format!("could not read records file: {}", error))?;
+                let scale: f32 = match sub_m.value_of("winrate-scale") {
+                    Some(scale) => scale.parse().map_err(|error| format!("invalid winrate scale: {}", error))?,
+                    None => 400f32,
+                };
+                standings_from_records(&records, default_rating, scale)
+            } else {
+                let teams_path = sub_m.value_of("teams").unwrap();
+                let mut contents = String::new();
+                if teams_path == "-" {
+                    io::stdin().read_to_string(&mut contents)?;
+                } else {
+                    contents = fs::read_to_string(teams_path)
+                        .map_err(|error| format!("could not read teams file '{}': {}", teams_path, error))?;
+                }
+                let teams = parse_team_list(&contents)
+                    .map_err(|error| format!("could not parse teams file '{}': {}", teams_path, error))?;
+                standings_from_team_list(&teams, default_rating)
             };
 
+            write_standings_to_path(Path::new(output_path), &standings)
+                .map_err(|error| format!("could not write standings file '{}': {}", output_path, error))?;
+        },
+        ("repl", Some(sub_m)) => {
+            let standings_path = sub_m.value_of("standings").unwrap();
+            let output_path = sub_m.value_of("output").unwrap();
+            let config_paths = config_paths_for(sub_m).unwrap_or_else(|| vec![PathBuf::from("config.json")]);
+            let config_paths: Vec<&Path> = config_paths.iter().map(PathBuf::as_path).collect();
+            if standings_path == "-" {
+                return Err("the standings cannot be read from stdin, it is used for entering results".into());
+            }
+
+            let standings_file = parse_standings_file_from_path(Path::new(standings_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+            let config = parse_layered_config(&config_paths)
+                .map_err(|error| format!("could not read config file: {}", error))?;
+            config.validate()?;
+
+            let stdin = io::stdin();
+            let final_standings = run_repl(stdin.lock(), io::stdout(), standings_file, &config, Path::new(output_path))?;
+
+            write_standings_file_to_path(Path::new(output_path), &final_standings)
+                .map_err(|error| format!("could not write standings file '{}': {}", output_path, error))?;
+        },
+        ("merge", Some(sub_m)) => {
+            let first_path = sub_m.value_of("first").unwrap();
+            let second_path = sub_m.value_of("second").unwrap();
+            let output_path = sub_m.value_of("output").unwrap();
+            let strategy: MergeStrategy = sub_m.value_of("strategy").unwrap().parse()?;
+            if first_path == "-" && second_path == "-" {
+                return Err("only one of the standings files can be read from stdin".into());
+            }
+
+            let first = parse_standings_file_from_path(Path::new(first_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+            let second = parse_standings_file_from_path(Path::new(second_path))
+                .map_err(|error| format!("could not read standings file: {}", error))?;
+            let merged = merge_standings(&first, &second, strategy);
+
+            let write_result = if sub_m.is_present("with-uncertainty") {
+                write_type_to_path(Path::new(output_path), &standings_with_uncertainty(&merged.standings, &merged.games_played, &merged.display_names, &merged.divisions))
+            } else {
+                write_standings_to_path(Path::new(output_path), &merged.standings)
+            };
+            write_result
+                .map_err(|error| format!("could not write standings file '{}': {}", output_path, error))?;
         },
         (&_, _) => {},
     };
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,206 @@
+//! An interactive session for entering results one at a time, such as during a live event.
+
+use crate::{format_leaderboard_with_games, get_match_update, write_standings_file_to_path, Configuration, MatchResult, StandingsFile};
+use std::collections::hash_map::Entry;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// A line of input to the session.
+#[derive(Debug, PartialEq)]
+pub enum ReplCommand {
+    /// `winner loser [series]`, the series defaulting to a Bo1.
+    Match(String, String, crate::SeriesKind),
+    /// `:add TEAM` adds a team at the default rating.
+    Add(String),
+    /// `:standings` prints the leaderboard.
+    Standings,
+    /// `:save` writes the standings to the output file.
+    Save,
+    /// `:quit` ends the session.
+    Quit,
+    /// A blank line.
+    Empty,
+}
+
+pub fn parse_repl_line(line: &str) -> Result<ReplCommand, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    match words.as_slice() {
+        [] => Ok(ReplCommand::Empty),
+        [":quit"] | [":q"] => Ok(ReplCommand::Quit),
+        [":save"] => Ok(ReplCommand::Save),
+        [":standings"] => Ok(ReplCommand::Standings),
+        [":add", team] => Ok(ReplCommand::Add(team.to_string())),
+        [command, ..] if command.starts_with(':') => Err(format!("unknown command `{}`, expected :add TEAM, :standings, :save or :quit", command)),
+        [winner, loser] => Ok(ReplCommand::Match(winner.to_string(), loser.to_string(), crate::SeriesKind::Bo1)),
+        [winner, loser, series] => Ok(ReplCommand::Match(winner.to_string(), loser.to_string(), series.parse()?)),
+        _ => Err("expected `winner loser [series]`".to_string()),
+    }
+}
+
+/// Reads commands from `input` until `:quit` or the end of input, applying each match to
+/// the standings in `file` and writing the new ratings of both teams to `output`. The games played
+/// carry on from the standings file, so established teams are not rated with the provisional k.
+///
+/// Matches naming a team that is not in the standings are rejected rather than creating the team,
+/// so a typo during an event does not add a new team, `:add` adds one deliberately. Team names
+/// are resolved through the configuration's aliases first. Problems with a line are reported to
+/// `output` and the session carries on. `:save` writes the extended form, keeping the games played,
+/// display names and divisions. Returns the final standings file.
+pub fn run_repl(input: impl BufRead, mut output: impl Write, mut file: StandingsFile, configuration: &Configuration, output_path: &Path) -> Result<StandingsFile, Box<dyn Error>> {
+    for line in input.lines() {
+        let command = match parse_repl_line(&line?) {
+            Ok(command) => command,
+            Err(error) => {
+                writeln!(output, "error: {}", error)?;
+                continue;
+            },
+        };
+
+        match command {
+            ReplCommand::Match(winner, loser, series) => {
+                let winner = configuration.canonical_team(&winner).to_string();
+                let loser = configuration.canonical_team(&loser).to_string();
+                if let Some(unknown) = [&winner, &loser].iter().find(|team| !file.standings.contains_key(**team)) {
+                    writeln!(output, "error: unknown team '{}', use :add {} to add it", unknown, unknown)?;
+                    continue;
+                }
+
+                let result = MatchResult { winner, loser, series, ..Default::default() };
+                match get_match_update(&result, &file.standings, &file.games_played, configuration) {
+                    Ok(update) => {
+                        update.apply_to(&mut file.standings);
+                        update.record_games_played(&mut file.games_played);
+                        writeln!(
+                            output,
+                            "{} {:.1} ({:+.1}), {} {:.1} ({:+.1})",
+                            update.winner, file.standings[&update.winner], update.winner_delta,
+                            update.loser, file.standings[&update.loser], update.loser_delta,
+                        )?;
+                    },
+                    Err(error) => writeln!(output, "error: {}", error)?,
+                }
+            },
+            ReplCommand::Add(team) => {
+                match file.standings.entry(configuration.canonical_team(&team).to_string()) {
+                    Entry::Occupied(entry) => writeln!(output, "error: team '{}' is already in the standings", entry.key())?,
+                    Entry::Vacant(entry) => {
+                        writeln!(output, "added {} at {:.1}", entry.key(), configuration.default_rating())?;
+                        entry.insert(configuration.default_rating());
+                    },
+                }
+            },
+            ReplCommand::Standings => write!(output, "{}", format_leaderboard_with_games(&file.standings, &file.games_played))?,
+            ReplCommand::Save => match write_standings_file_to_path(output_path, &file) {
+                Ok(()) => writeln!(output, "saved to {}", output_path.display())?,
+                Err(error) => writeln!(output, "error: could not write standings file '{}': {}", output_path.display(), error)?,
+            },
+            ReplCommand::Quit => break,
+            ReplCommand::Empty => {},
+        }
+    }
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_standings_file_from_path, GamesPlayed, SeriesKind, Standings};
+
+    fn test_config() -> Configuration {
+        serde_json::from_value(serde_json::json!({
+            "bo1_score": 1,
+            "bo3_score": 1.25,
+            "bo5_score": 1.5,
+            "k_brackets": [{ "start": 0, "k": 32 }],
+        })).unwrap()
+    }
+
+    #[test]
+    fn parses_matches_and_commands() {
+        assert!(matches!(parse_repl_line("C9 TSM"), Ok(ReplCommand::Match(ref winner, ref loser, SeriesKind::Bo1)) if winner == "C9" && loser == "TSM"));
+        assert!(matches!(parse_repl_line("  C9 TSM bo3 "), Ok(ReplCommand::Match(_, _, SeriesKind::Bo3))));
+        assert_eq!(parse_repl_line(":add TL"), Ok(ReplCommand::Add("TL".to_string())));
+        assert_eq!(parse_repl_line(":quit"), Ok(ReplCommand::Quit));
+        assert_eq!(parse_repl_line(""), Ok(ReplCommand::Empty));
+        assert!(parse_repl_line(":undo").is_err());
+        assert!(parse_repl_line("C9 TSM final").is_err());
+        assert!(parse_repl_line("C9").is_err());
+    }
+
+    #[test]
+    fn applies_matches_until_quit() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let input = "C9 TSM\nC9 TL\n:add TL\nTL C9 bo3\n:quit\nTSM C9\n";
+        let mut output = Vec::new();
+
+        let final_standings = run_repl(input.as_bytes(), &mut output, StandingsFile { standings, ..Default::default() }, &test_config(), Path::new("unused.json")).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "C9 1516.0 (+16.0), TSM 1484.0 (-16.0)");
+        assert_eq!(lines[1], "error: unknown team 'TL', use :add TL to add it");
+        assert_eq!(lines[2], "added TL at 1500.0");
+        assert!(lines[3].starts_with("TL "));
+        assert_eq!(lines.len(), 4);
+        assert_eq!(final_standings.standings["TSM"], 1484f32);
+        assert!(final_standings.standings["TL"] > 1500f32);
+    }
+
+    #[test]
+    fn aliases_resolve_to_their_team() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let mut config = test_config();
+        config.aliases.insert("Cloud9".to_string(), "C9".to_string());
+        let input = "Cloud9 TSM\n:add Cloud9\n";
+        let mut output = Vec::new();
+
+        let final_standings = run_repl(input.as_bytes(), &mut output, StandingsFile { standings, ..Default::default() }, &config, Path::new("unused.json")).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines, vec!["C9 1516.0 (+16.0), TSM 1484.0 (-16.0)", "error: team 'C9' is already in the standings"]);
+        assert_eq!(final_standings.standings.len(), 2);
+    }
+
+    #[test]
+    fn established_teams_keep_their_games_played() {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1500f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        let mut games_played = GamesPlayed::new();
+        games_played.insert("C9".to_string(), 20);
+        games_played.insert("TSM".to_string(), 20);
+        let config = Configuration { provisional_games: 10, provisional_k: Some(64f32), ..test_config() };
+        let mut output = Vec::new();
+
+        run_repl("C9 TSM\n".as_bytes(), &mut output, StandingsFile { standings, games_played, ..Default::default() }, &config, Path::new("unused.json")).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "C9 1516.0 (+16.0), TSM 1484.0 (-16.0)\n");
+    }
+
+    #[test]
+    fn saved_standings_keep_games_names_and_divisions() {
+        let path = std::env::temp_dir().join("elo_repl_saved_standings.json");
+        std::fs::write(&path, r#"{
+            "C9": { "rating": 1500, "games": 20, "display_name": "Cloud9", "division": "NA" },
+            "TSM": { "rating": 1500, "games": 20, "division": "NA" }
+        }"#).unwrap();
+        let file = parse_standings_file_from_path(&path).unwrap();
+        let mut output = Vec::new();
+
+        let final_file = run_repl("C9 TSM\n:save\n".as_bytes(), &mut output, file, &test_config(), &path).unwrap();
+        let saved = parse_standings_file_from_path(&path).unwrap();
+
+        assert_eq!(saved, final_file);
+        assert_eq!(saved.games_played["C9"], 21);
+        assert_eq!(saved.display_names["C9"], "Cloud9");
+        assert_eq!(saved.divisions["TSM"], "NA");
+        assert_eq!(saved.standings["C9"], 1516f32);
+    }
+}
@@ -0,0 +1,114 @@
+//! JSON schemas for the input files, used to report every structural problem in a file at once
+//! rather than the first error serde runs into.
+
+use crate::{has_extension, is_ndjson, parse_match_results_from_path, parse_type_from_path};
+use std::path::Path;
+
+const CONFIG_SCHEMA: &str = include_str!("../schemas/config.schema.json");
+const STANDINGS_SCHEMA: &str = include_str!("../schemas/standings.schema.json");
+const MATCHES_SCHEMA: &str = include_str!("../schemas/matches.schema.json");
+
+/// The kinds of input file that have a schema.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputKind {
+    Configuration,
+    Standings,
+    Matches,
+}
+
+impl InputKind {
+    /// The bundled JSON schema for this kind of file.
+    pub fn schema(self) -> &'static str {
+        match self {
+            InputKind::Configuration => CONFIG_SCHEMA,
+            InputKind::Standings => STANDINGS_SCHEMA,
+            InputKind::Matches => MATCHES_SCHEMA,
+        }
+    }
+}
+
+/// Every place `value` does not match the schema of `kind`, each as the JSON pointer of the field
+/// followed by what is wrong with it.
+pub fn validate_value(kind: InputKind, value: &serde_json::Value) -> Vec<String> {
+    let schema: serde_json::Value = serde_json::from_str(kind.schema()).expect("bundled schemas are valid JSON");
+    let validator = jsonschema::validator_for(&schema).expect("bundled schemas are valid schemas");
+
+    validator
+        .iter_errors(value)
+        .map(|error| {
+            let pointer = error.instance_path().to_string();
+            let pointer = if pointer.is_empty() { "/".to_string() } else { pointer };
+            format!("{}: {}", pointer, error)
+        })
+        .collect()
+}
+
+/// Every structural problem in the file at `path`. A file that cannot be read or parsed at all
+/// is reported as a single problem, which includes the line and column for syntax errors.
+///
+/// CSV and NDJSON match files have no schema, they are only checked to parse.
+pub fn validate_file(kind: InputKind, path: &Path) -> Vec<String> {
+    if kind == InputKind::Matches && (has_extension(path, "csv") || is_ndjson(path)) {
+        return match parse_match_results_from_path(path) {
+            Ok(_) => Vec::new(),
+            Err(error) => vec![error.to_string()],
+        };
+    }
+
+    match parse_type_from_path::<serde_json::Value>(path) {
+        Ok(value) => validate_value(kind, &value),
+        Err(error) => vec![error.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn examples_are_valid() {
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        assert!(validate_file(InputKind::Configuration, &examples.join("config.json")).is_empty());
+        assert!(validate_file(InputKind::Standings, &examples.join("standings.json")).is_empty());
+        assert!(validate_file(InputKind::Matches, &examples.join("results.json")).is_empty());
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_field() {
+        let matches = json!([
+            { "winner": "C9", "loser": "TSM", "series": "Bo1" },
+            { "winner": "C9", "loser": 4, "series": "best of 2" },
+            { "winer": "C9", "loser": "TSM", "series": { "Custom": 9 } },
+        ]);
+
+        let problems = validate_value(InputKind::Matches, &matches);
+        assert!(problems.iter().any(|problem| problem.starts_with("/1/loser: ")));
+        assert!(problems.iter().any(|problem| problem.starts_with("/1/series: ")));
+        assert!(problems.iter().any(|problem| problem.starts_with("/2: ") && problem.contains("winner")));
+        assert!(problems.iter().all(|problem| !problem.starts_with("/0")));
+    }
+
+    #[test]
+    fn config_and_standings_types_are_checked() {
+        let config = json!({ "bo1_score": 1, "bo3_score": "1.25", "bo5_score": 1.5, "k_brackets": [{ "start": -1, "k": 32 }], "k_selection": "random" });
+        assert_eq!(validate_value(InputKind::Configuration, &config).len(), 3);
+
+        let standings = json!({ "C9": 1500, "TSM": "1400" });
+        let problems = validate_value(InputKind::Standings, &standings);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("/TSM: "));
+    }
+
+    #[test]
+    fn optional_config_fields_accept_null() {
+        let config = json!({
+            "bo1_score": 1, "bo3_score": 1.25, "bo5_score": 1.5, "k_brackets": [{ "start": 0, "k": 32 }],
+            "bo7_score": null, "default_rating": null, "rating_floor": null, "rating_ceiling": null,
+            "ceiling_compression": null, "max_delta_per_match": null, "provisional_k": null,
+            "home_advantage": null, "upset_factor": null, "repeat_matchup_decay": null,
+        });
+        assert!(validate_value(InputKind::Configuration, &config).is_empty());
+        assert!(serde_json::from_value::<crate::Configuration>(config).is_ok());
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{get_match_update_with_meetings, EloError, sorted_standings, Configuration, GamesPlayed, MatchResult, Meetings, SeriesKind, Standings};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// An upcoming match between two teams whose result is not known yet.
+#[derive(serde::Deserialize, Clone)]
+pub struct Matchup {
+    pub team_a: String,
+    pub team_b: String,
+    #[serde(default)]
+    pub series: SeriesKind,
+    /// Unrated matchups, such as exhibitions, are left out of the simulation.
+    #[serde(default = "crate::default_rated")]
+    pub rated: bool,
+}
+
+/// For every team, the probability of finishing in each position, index 0 being first.
+pub type FinishProbabilities = HashMap<String, Vec<f32>>;
+
+/// Plays `schedule` out `iterations` times on top of `standings` and tallies where every team
+/// finishes by final rating.
+///
+/// The winner of each game is drawn with the expected probability from the configuration's
+/// `EloScale` on the ratings at that point of the simulated season, and ratings are updated after every game
+/// as they would be for real results. Each iteration draws from its own stream of a ChaCha RNG
+/// seeded with `seed`, so the same seed always gives the same probabilities.
+///
+/// Iterations run in parallel on the rayon thread pool. Since every iteration has its own stream
+/// and the finishes are tallied as counts, the result does not depend on the number of threads.
+///
+/// Teams in the schedule are resolved through the configuration's aliases, so an alias plays as
+/// the team it stands for. Like `update`, unrated matchups and matchups against ignored teams are
+/// not played, and ignored teams are left out of the finishes so they take no one's position.
+///
+/// Fails with `EloError::InvalidConfig` when `iterations` is 0, since no probabilities can be
+/// worked out from no seasons, and if a simulated game cannot be applied, the same as
+/// `apply_match_results`.
+pub fn simulate_season(standings: &Standings, schedule: &[Matchup], configuration: &Configuration, iterations: u32, seed: u64) -> Result<FinishProbabilities, EloError> {
+    if iterations == 0 {
+        return Err(EloError::InvalidConfig("a season must be simulated at least once, got 0 iterations".to_string()));
+    }
+
+    let schedule = rated_schedule(schedule, configuration);
+    let schedule = schedule.as_slice();
+    let standings: Standings = standings
+        .iter()
+        .filter(|(team, _)| !configuration.ignored_teams.contains(configuration.canonical_team(team)))
+        .map(|(team, rating)| (team.clone(), *rating))
+        .collect();
+    let standings = &standings;
+    let mut teams: Vec<String> = standings.keys().cloned().collect();
+    for matchup in schedule {
+        teams.push(matchup.team_a.clone());
+        teams.push(matchup.team_b.clone());
+    }
+    teams.sort();
+    teams.dedup();
+
+    let team_indices: HashMap<&String, usize> = teams.iter().enumerate().map(|(index, team)| (team, index)).collect();
+    let no_finishes = || vec![vec![0u32; teams.len()]; teams.len()];
+
+    // `finishes[team][position]` counts how often the team with that index finished there.
+    let finishes = (0..iterations)
+        .into_par_iter()
+        .map(|iteration| simulate_iteration(standings, schedule, configuration, seed, iteration))
+        .try_fold(no_finishes, |mut finishes, final_standings| {
+            for (position, (team, _)) in sorted_standings(&final_standings?).into_iter().enumerate() {
+                finishes[team_indices[team]][position] += 1;
+            }
+            Ok::<_, EloError>(finishes)
+        })
+        .try_reduce(no_finishes, |mut finishes, other_finishes| {
+            for (counts, other_counts) in finishes.iter_mut().zip(other_finishes) {
+                for (count, other_count) in counts.iter_mut().zip(other_counts) {
+                    *count += other_count;
+                }
+            }
+            Ok(finishes)
+        })?;
+
+    let probabilities = teams
+        .into_iter()
+        .zip(finishes)
+        .map(|(team, counts)| {
+            let probabilities = counts.into_iter().map(|count| count as f32 / iterations as f32).collect();
+            (team, probabilities)
+        })
+        .collect();
+
+    Ok(probabilities)
+}
+
+/// The matchups of `schedule` that would be rated, with both teams under the names their ratings
+/// are stored under.
+fn rated_schedule(schedule: &[Matchup], configuration: &Configuration) -> Vec<Matchup> {
+    schedule
+        .iter()
+        .map(|matchup| Matchup {
+            team_a: configuration.canonical_team(&matchup.team_a).to_string(),
+            team_b: configuration.canonical_team(&matchup.team_b).to_string(),
+            ..matchup.clone()
+        })
+        .filter(|matchup| {
+            let ignored = |team: &String| configuration.ignored_teams.contains(team);
+            matchup.rated && !ignored(&matchup.team_a) && !ignored(&matchup.team_b)
+        })
+        .collect()
+}
+
+fn simulate_iteration(standings: &Standings, schedule: &[Matchup], configuration: &Configuration, seed: u64, iteration: u32) -> Result<Standings, EloError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(iteration as u64);
+
+    let mut simulated_standings = standings.clone();
+    for matchup in schedule {
+        simulated_standings.entry(matchup.team_a.clone()).or_insert(configuration.default_rating());
+        simulated_standings.entry(matchup.team_b.clone()).or_insert(configuration.default_rating());
+    }
+    let mut games_played = GamesPlayed::new();
+    let mut meetings = Meetings::default();
+
+    for matchup in schedule {
+        let (p_a, _) = configuration.expected_probabilities(simulated_standings[&matchup.team_a], simulated_standings[&matchup.team_b]);
+        let (winner, loser) = if rng.gen::<f32>() < p_a {
+            (&matchup.team_a, &matchup.team_b)
+        } else {
+            (&matchup.team_b, &matchup.team_a)
+        };
+
+        let result = MatchResult {
+            winner: winner.clone(),
+            loser: loser.clone(),
+            series: matchup.series,
+            ..Default::default()
+        };
+        let update = get_match_update_with_meetings(&result, &simulated_standings, &games_played, &meetings, configuration)?;
+        update.apply_to(&mut simulated_standings);
+        update.record_games_played(&mut games_played);
+        if configuration.repeat_matchup_decay.is_some() {
+            meetings.record(&update);
+        }
+    }
+
+    Ok(simulated_standings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KBracket;
+
+    fn test_config() -> Configuration {
+        serde_json::from_value(serde_json::json!({
+            "bo1_score": 1,
+            "bo3_score": 1.25,
+            "bo5_score": 1.5,
+            "k_brackets": [{"start": 0, "k": 32}],
+        })).unwrap()
+    }
+
+    fn matchup(team_a: &str, team_b: &str) -> Matchup {
+        Matchup {
+            team_a: team_a.to_string(),
+            team_b: team_b.to_string(),
+            series: SeriesKind::Bo1,
+            rated: true,
+        }
+    }
+
+    fn test_season() -> (Standings, Vec<Matchup>) {
+        let mut standings = Standings::new();
+        standings.insert("C9".to_string(), 1700f32);
+        standings.insert("TSM".to_string(), 1500f32);
+        standings.insert("CLG".to_string(), 1300f32);
+
+        let schedule = vec![matchup("C9", "TSM"), matchup("TSM", "CLG"), matchup("CLG", "C9")];
+        (standings, schedule)
+    }
+
+    #[test]
+    fn simulation_is_reproducible() {
+        let (standings, schedule) = test_season();
+        let config = test_config();
+
+        let first = simulate_season(&standings, &schedule, &config, 500, 7).unwrap();
+        let second = simulate_season(&standings, &schedule, &config, 500, 7).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn finish_probabilities_are_distributions() {
+        let (standings, schedule) = test_season();
+        let config = Configuration { k_brackets: vec![KBracket { start: 0, k: 200f32 }], ..test_config() };
+
+        let probabilities = simulate_season(&standings, &schedule, &config, 1000, 42).unwrap();
+        for team_probabilities in probabilities.values() {
+            assert_eq!(team_probabilities.len(), 3);
+            assert!((team_probabilities.iter().sum::<f32>() - 1f32).abs() < 1e-4);
+        }
+        assert!(probabilities["C9"][0] > probabilities["TSM"][0]);
+        assert!(probabilities["TSM"][0] > probabilities["CLG"][0]);
+    }
+
+    #[test]
+    fn thread_count_does_not_change_results() {
+        let (standings, schedule) = test_season();
+        let config = test_config();
+        let simulate_on = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| simulate_season(&standings, &schedule, &config, 2000, 11).unwrap())
+        };
+
+        let single_threaded = simulate_on(1);
+        assert_eq!(simulate_on(4), single_threaded);
+        assert_eq!(simulate_on(7), single_threaded);
+    }
+
+    #[test]
+    fn ignored_teams_and_unrated_matchups_are_left_out() {
+        let (mut standings, schedule) = test_season();
+        standings.insert("BYE".to_string(), 2000f32);
+        let mut config = test_config();
+        config.ignored_teams.insert("BYE".to_string());
+        let mut padded_schedule = schedule.clone();
+        padded_schedule.insert(1, matchup("BYE", "CLG"));
+        padded_schedule.push(Matchup { rated: false, ..matchup("CLG", "C9") });
+
+        let probabilities = simulate_season(&standings, &padded_schedule, &config, 500, 7).unwrap();
+        assert!(!probabilities.contains_key("BYE"));
+        assert!(probabilities.values().all(|team_probabilities| team_probabilities.len() == 3));
+
+        let (standings, _) = test_season();
+        assert_eq!(probabilities, simulate_season(&standings, &schedule, &config, 500, 7).unwrap());
+    }
+
+    #[test]
+    fn zero_iterations_are_rejected() {
+        let (standings, schedule) = test_season();
+        let error = simulate_season(&standings, &schedule, &test_config(), 0, 7).unwrap_err();
+        assert!(matches!(error, EloError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn aliases_play_as_their_team() {
+        let (standings, schedule) = test_season();
+        let mut config = test_config();
+        config.aliases.insert("Cloud9".to_string(), "C9".to_string());
+        let aliased_schedule = vec![matchup("Cloud9", "TSM"), matchup("TSM", "CLG"), matchup("CLG", "Cloud9")];
+
+        let aliased = simulate_season(&standings, &aliased_schedule, &config, 500, 7).unwrap();
+        assert!(!aliased.contains_key("Cloud9"));
+        assert_eq!(aliased, simulate_season(&standings, &schedule, &config, 500, 7).unwrap());
+    }
+}
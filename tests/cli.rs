@@ -0,0 +1,704 @@
+use elo::fixtures::generate_season;
+use elo::{apply_match_results, Configuration};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn elo() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_elo"))
+}
+
+/// Creates an empty scratch directory unique to `name` under the system temp directory.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("elo_cli_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, name: &str, contents: &str) -> String {
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+const CONFIG: &str = r#"{
+    "bo1_score": 1,
+    "bo3_score": 1.25,
+    "bo5_score": 1.5,
+    "k_brackets": [{"start": 0, "k": 32}, {"start": 400, "k": 28}]
+}"#;
+
+const STANDINGS: &str = r#"{"C9": 950, "TSM": 500, "CLG": 100}"#;
+
+const WEEK_1: &str = r#"[{"winner": "CLG", "loser": "C9", "series": "Bo1"}]"#;
+const WEEK_2: &str = r#"[{"winner": "TSM", "loser": "C9", "series": "Bo3"}]"#;
+
+fn read_standings(path: &str) -> serde_json::Value {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn multiple_match_files_equal_sequential_runs() {
+    let dir = scratch_dir("multiple_match_files");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let week_2 = write(&dir, "week_2.json", WEEK_2);
+    let intermediate = dir.join("intermediate.json").to_str().unwrap().to_string();
+    let sequential = dir.join("sequential.json").to_str().unwrap().to_string();
+    let combined = dir.join("combined.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &intermediate]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &intermediate, "-m", &week_2, "-o", &sequential]).status().unwrap();
+    assert!(status.success());
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-m", &week_2, "-o", &combined]).status().unwrap();
+    assert!(status.success());
+    assert_eq!(read_standings(&sequential), read_standings(&combined));
+
+    let comma_separated = format!("{},{}", week_1, week_2);
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &comma_separated, "-o", &combined]).status().unwrap();
+    assert!(status.success());
+    assert_eq!(read_standings(&sequential), read_standings(&combined));
+}
+
+#[test]
+fn dry_run_prints_standings_without_output_file() {
+    let dir = scratch_dir("dry_run");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+
+    let output = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "--dry-run"]).output().unwrap();
+    assert!(output.status.success());
+
+    let printed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(printed["CLG"].as_f64().unwrap() > 100f64);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+}
+
+#[test]
+fn snapshots_are_written_every_n_matches() {
+    let dir = scratch_dir("snapshots");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let matches = write(&dir, "matches.json", r#"[
+        {"winner": "CLG", "loser": "C9", "series": "Bo1"},
+        {"winner": "TSM", "loser": "C9", "series": "Bo1"},
+        {"winner": "C9", "loser": "CLG", "series": "Bo1"},
+        {"winner": "C9", "loser": "TSM", "series": "Bo1"},
+        {"winner": "TSM", "loser": "CLG", "series": "Bo1"}
+    ]"#);
+    let output = write(&dir, "output.json", "");
+    let snapshot_dir = dir.join("snapshots");
+
+    let status = elo()
+        .args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &output])
+        .args(["--snapshot-every", "2", "--snapshot-dir", snapshot_dir.to_str().unwrap()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut snapshots: Vec<String> = fs::read_dir(&snapshot_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    snapshots.sort();
+    assert_eq!(snapshots, vec!["standings_0002.json", "standings_0004.json"]);
+    assert_ne!(read_standings(snapshot_dir.join("standings_0004.json").to_str().unwrap()), read_standings(&output));
+}
+
+#[test]
+fn validate_only_reports_every_problem() {
+    let dir = scratch_dir("validate_only");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", r#"{"C9": 950, "TSM": "500"}"#);
+    let matches = write(&dir, "matches.json", r#"[{"winner": "CLG", "looser": "C9", "series": "Bo1"}]"#);
+
+    let output = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "--validate-only"]).output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("{}: /TSM: ", standings)));
+    assert!(stderr.contains(&format!("{}: /0: ", matches)));
+    assert!(stderr.contains("found 3 problems"));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+    let status = elo().args(["update", "-c", &config, "-s", &write(&dir, "standings.json", STANDINGS), "-m", &write(&dir, "matches.json", WEEK_1), "--validate-only"]).status().unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn output_dash_writes_only_standings_to_stdout() {
+    let dir = scratch_dir("output_stdout");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let expected = dir.join("expected.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &expected]).status().unwrap();
+    assert!(status.success());
+
+    let output = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", "-", "--leaderboard", "--log"]).output().unwrap();
+    assert!(output.status.success());
+    let printed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(printed, read_standings(&expected));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Rank"));
+}
+
+#[test]
+fn round_zero_writes_whole_ratings() {
+    let dir = scratch_dir("round");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &output, "--round", "0"]).status().unwrap();
+    assert!(status.success());
+
+    let ratings = read_standings(&output);
+    for rating in ratings.as_object().unwrap().values() {
+        assert_eq!(rating.as_f64().unwrap().fract(), 0f64);
+    }
+    assert_ne!(ratings["CLG"].as_f64().unwrap(), 100f64);
+}
+
+#[test]
+fn uncertainty_output_round_trips_into_next_run() {
+    let dir = scratch_dir("uncertainty_round_trip");
+    let config = write(&dir, "config.json", r#"{
+        "bo1_score": 1,
+        "bo3_score": 1.25,
+        "bo5_score": 1.5,
+        "k_brackets": [{"start": 0, "k": 32}],
+        "provisional_games": 2,
+        "provisional_k": 64
+    }"#);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let week_2 = write(&dir, "week_2.json", WEEK_2);
+    let intermediate = dir.join("intermediate.json").to_str().unwrap().to_string();
+    let sequential = dir.join("sequential.json").to_str().unwrap().to_string();
+    let combined = dir.join("combined.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &intermediate, "--with-uncertainty"]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &intermediate, "-m", &week_2, "-o", &sequential, "--with-uncertainty"]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-m", &week_2, "-o", &combined, "--with-uncertainty"]).status().unwrap();
+    assert!(status.success());
+
+    let sequential = read_standings(&sequential);
+    assert_eq!(sequential, read_standings(&combined));
+    assert_eq!(sequential["C9"]["games"], 2);
+}
+
+#[test]
+fn report_deltas_are_after_minus_before() {
+    let dir = scratch_dir("report");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let matches = write(&dir, "matches.json", r#"[
+        {"winner": "CLG", "loser": "C9", "series": "Bo1"},
+        {"winner": "TL", "loser": "TSM", "series": "Bo3"}
+    ]"#);
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+    let report_path = dir.join("report.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &output, "--report", &report_path]).status().unwrap();
+    assert!(status.success());
+
+    let report = read_standings(&report_path);
+    assert_eq!(report["matches_applied"], 2);
+    assert_eq!(report["new_teams"], serde_json::json!(["TL"]));
+    assert_eq!(report["configuration"]["bo3_score"], 1.25);
+    assert_eq!(report["warnings"].as_array().unwrap().len(), 1);
+    for (team, team_report) in report["teams"].as_object().unwrap() {
+        let after = team_report["after"].as_f64().unwrap();
+        let before = team_report["before"].as_f64().unwrap_or(1500f64);
+        assert!((team_report["delta"].as_f64().unwrap() - (after - before)).abs() < 1e-3, "{}", team);
+        assert_eq!(after, read_standings(&output)[team].as_f64().unwrap());
+    }
+}
+
+#[test]
+fn compare_fails_when_ratings_drift_from_reference() {
+    let dir = scratch_dir("compare");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let matches = write(&dir, "matches.json", WEEK_1);
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &output]).status().unwrap();
+    assert!(status.success());
+
+    let compare = |reference: &str, tolerance: &str| {
+        elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "--dry-run", "--compare", reference, "--tolerance", tolerance]).output().unwrap()
+    };
+    assert!(compare(&output, "0").status.success());
+
+    let mut edited = read_standings(&output);
+    edited["TSM"] = serde_json::json!(edited["TSM"].as_f64().unwrap() + 2f64);
+    let edited = write(&dir, "edited.json", &edited.to_string());
+    assert!(compare(&edited, "5").status.success());
+    let drifted = compare(&edited, "1");
+    assert!(!drifted.status.success());
+    let stderr = String::from_utf8(drifted.stderr).unwrap();
+    assert!(stderr.contains("TSM: computed 500.0, reference 502.0"));
+    assert!(!stderr.contains("C9:"));
+}
+
+#[test]
+fn ad_hoc_match_equals_single_match_file() {
+    let dir = scratch_dir("ad_hoc_match");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let matches = write(&dir, "matches.json", WEEK_2);
+    let from_file = dir.join("from_file.json").to_str().unwrap().to_string();
+    let ad_hoc = dir.join("ad_hoc.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &from_file]).status().unwrap();
+    assert!(status.success());
+    let status = elo()
+        .args(["update", "-c", &config, "-s", &standings, "--match-winner", "TSM", "--match-loser", "C9", "--match-series", "bo3", "-o", &ad_hoc])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(read_standings(&ad_hoc), read_standings(&from_file));
+
+    let both = elo()
+        .args(["update", "-c", &config, "-s", &standings, "-m", &matches, "--match-winner", "TSM", "--match-loser", "C9", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(!both.status.success());
+}
+
+#[test]
+fn ndjson_matches_equal_json_matches() {
+    let dir = scratch_dir("ndjson");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let json = write(&dir, "matches.json", r#"[
+        {"winner": "CLG", "loser": "C9", "series": "Bo1"},
+        {"winner": "TSM", "loser": "C9", "series": "Bo3"},
+        {"winner": "CLG", "loser": "TSM", "series": "Bo1"}
+    ]"#);
+    let ndjson = "{\"winner\": \"CLG\", \"loser\": \"C9\", \"series\": \"Bo1\"}\n{\"winner\": \"TSM\", \"loser\": \"C9\", \"series\": \"Bo3\"}\n\n{\"winner\": \"CLG\", \"loser\": \"TSM\", \"series\": \"Bo1\"}\n";
+    let ndjson_path = write(&dir, "matches.ndjson", ndjson);
+    let from_json = dir.join("from_json.json").to_str().unwrap().to_string();
+    let from_ndjson = dir.join("from_ndjson.json").to_str().unwrap().to_string();
+    let from_stdin = dir.join("from_stdin.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &json, "-o", &from_json]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &ndjson_path, "-o", &from_ndjson]).status().unwrap();
+    assert!(status.success());
+
+    let mut child = elo()
+        .args(["update", "-c", &config, "-s", &standings, "-m", "-", "--matches-format", "ndjson", "-o", &from_stdin])
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(ndjson.as_bytes()).unwrap();
+    assert!(child.wait().unwrap().success());
+
+    assert_eq!(read_standings(&from_ndjson), read_standings(&from_json));
+    assert_eq!(read_standings(&from_stdin), read_standings(&from_json));
+}
+
+#[test]
+fn top_only_writes_the_highest_rated_teams() {
+    let dir = scratch_dir("top");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", r#"{"C9": 950, "TSM": 500, "CLG": 100, "TL": 700, "FLY": 300}"#);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let full = dir.join("full.json").to_str().unwrap().to_string();
+    let top = dir.join("top.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &full]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &top, "--top", "3"]).status().unwrap();
+    assert!(status.success());
+
+    let full = read_standings(&full);
+    let mut ratings: Vec<(&String, f64)> = full.as_object().unwrap().iter().map(|(team, rating)| (team, rating.as_f64().unwrap())).collect();
+    ratings.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let expected: serde_json::Map<String, serde_json::Value> = ratings[..3].iter().map(|(team, _)| ((*team).clone(), full[team.as_str()].clone())).collect();
+
+    let top = read_standings(&top);
+    assert_eq!(top.as_object().unwrap().len(), 3);
+    assert_eq!(top, serde_json::Value::Object(expected));
+}
+
+#[test]
+fn strict_levels_on_a_match_with_a_missing_team() {
+    let dir = scratch_dir("strict_levels");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", r#"{"C9": 950, "TSM": 500}"#);
+    let matches = write(&dir, "matches.json", r#"[{"winner": "CLG", "loser": "C9", "series": "Bo1"}, {"winner": "TSM", "loser": "C9", "series": "Bo1"}]"#);
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+    let run = |strict: &[&str]| {
+        let _ = fs::remove_file(&output);
+        let mut args = vec!["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &output];
+        args.extend_from_slice(strict);
+        elo().args(&args).output().unwrap()
+    };
+
+    let off = run(&["--strict", "off"]);
+    assert!(off.status.success());
+    assert!(!String::from_utf8_lossy(&off.stderr).contains("CLG"));
+    assert!(read_standings(&output).get("CLG").is_some());
+
+    for warn in [&[][..], &["--strict", "warn"][..]] {
+        let warned = run(warn);
+        assert!(warned.status.success());
+        assert!(String::from_utf8_lossy(&warned.stderr).contains("warning: teams in matches but not in standings: CLG"));
+        assert!(read_standings(&output).get("CLG").is_some());
+    }
+
+    for error in [&["--strict", "error"][..], &["--strict"][..]] {
+        let failed = run(error);
+        assert!(!failed.status.success());
+        assert!(String::from_utf8_lossy(&failed.stderr).contains("teams in matches but not in standings: CLG"));
+        assert!(!Path::new(&output).exists());
+    }
+}
+
+#[test]
+fn check_reports_each_problem_without_writing_standings() {
+    let dir = scratch_dir("check");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let matches = write(&dir, "matches.json", r#"[
+        {"winner": "CLG", "loser": "C9", "series": "Bo1"},
+        {"winner": "FLY", "loser": "C9", "series": "Bo1"},
+        {"winner": "TSM", "loser": "C9", "series": "best of 3"},
+        {"winner": "TSM", "loser": "TSM", "series": "bo3"}
+    ]"#);
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+
+    let failed = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &output, "--check"]).output().unwrap();
+    assert!(!failed.status.success());
+    let report = String::from_utf8_lossy(&failed.stdout);
+    assert!(report.contains("Matches checked: 4\n"));
+    assert!(report.contains("Missing teams: 1\n  match 1: FLY\n"));
+    assert!(report.contains("Invalid series kinds: 1\n  match 2: \"best of 3\"\n"));
+    assert!(report.contains("Self matches: 1\n  match 3: TSM\n"));
+    assert!(report.contains("Result: fail"));
+    assert!(String::from_utf8_lossy(&failed.stderr).contains("found 3 problems in the matches"));
+    assert!(!Path::new(&output).exists());
+
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let passed = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "--check"]).output().unwrap();
+    assert!(passed.status.success());
+    assert!(String::from_utf8_lossy(&passed.stdout).contains("Result: pass"));
+
+    let exhibition = write(&dir, "exhibition.json", r#"[{"winner": "FLY", "loser": "C9", "series": "Bo1", "rated": false}]"#);
+    let passed = elo().args(["update", "-c", &config, "-s", &standings, "-m", &exhibition, "--check"]).output().unwrap();
+    assert!(passed.status.success());
+    assert!(String::from_utf8_lossy(&passed.stdout).contains("Missing teams: 0\n"));
+}
+
+#[test]
+fn check_reads_matches_like_update() {
+    let dir = scratch_dir("check_inputs");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let feed = write(&dir, "feed.txt", "{\"winner\": \"CLG\", \"loser\": \"C9\", \"series\": \"Bo1\"}\n{\"winner\": \"FLY\", \"loser\": \"C9\", \"series\": \"Bo1\"}\n");
+
+    let feed_check = elo().args(["update", "-c", &config, "-s", &standings, "-m", &feed, "--matches-format", "ndjson", "--check"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&feed_check.stdout).contains("Matches checked: 2\nMissing teams: 1\n  match 1: FLY\n"));
+
+    let ad_hoc_check = elo().args(["update", "-c", &config, "-s", &standings, "--match-winner", "FLY", "--match-loser", "C9", "--check"]).output().unwrap();
+    assert!(!ad_hoc_check.status.success());
+    assert!(String::from_utf8_lossy(&ad_hoc_check.stdout).contains("Matches checked: 1\nMissing teams: 1\n  match 0: FLY\n"));
+
+    let grouped_check = elo().args(["update", "-c", &config, "-s", &standings, "-m", &feed, "--group-games", "--check"]).output().unwrap();
+    assert!(!grouped_check.status.success());
+    assert!(String::from_utf8_lossy(&grouped_check.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn division_filter_hides_other_divisions_but_rates_every_match() {
+    let dir = scratch_dir("division");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", r#"{
+        "C9": {"rating": 950, "division": "NA"},
+        "TSM": {"rating": 500, "division": "NA"},
+        "G2": {"rating": 900, "division": "EU"},
+        "FNC": {"rating": 700, "division": "EU"}
+    }"#);
+    let matches = write(&dir, "matches.json", r#"[{"winner": "G2", "loser": "C9", "series": "Bo1"}, {"winner": "TSM", "loser": "FNC", "series": "Bo1"}]"#);
+    let full = dir.join("full.json").to_str().unwrap().to_string();
+    let na = dir.join("na.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &full]).status().unwrap();
+    assert!(status.success());
+    let output = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &na, "--division", "NA", "--leaderboard"]).output().unwrap();
+    assert!(output.status.success());
+
+    let full = read_standings(&full);
+    let na = read_standings(&na);
+    assert_eq!(na.as_object().unwrap().len(), 2);
+    assert_eq!(na["C9"], full["C9"]);
+    assert_eq!(na["TSM"], full["TSM"]);
+    assert!(na["C9"].as_f64().unwrap() < 950f64);
+    assert!(na["TSM"].as_f64().unwrap() > 500f64);
+    let leaderboard = String::from_utf8_lossy(&output.stdout);
+    assert!(leaderboard.contains("C9") && leaderboard.contains("TSM"));
+    assert!(!leaderboard.contains("G2") && !leaderboard.contains("FNC"));
+}
+
+#[test]
+fn generated_season_matches_the_library() {
+    let dir = scratch_dir("generated_season");
+    let season = generate_season(12, 400, 5);
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", &serde_json::to_string(&season.standings).unwrap());
+    let matches: Vec<serde_json::Value> = season.matches.iter()
+        .map(|result| serde_json::json!({"winner": result.winner, "loser": result.loser, "series": result.series}))
+        .collect();
+    let matches = write(&dir, "matches.json", &serde_json::to_string(&matches).unwrap());
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &output]).status().unwrap();
+    assert!(status.success());
+
+    let config: Configuration = serde_json::from_str(CONFIG).unwrap();
+    let expected = apply_match_results(&season.matches, &season.standings, &config).unwrap();
+    let written = read_standings(&output);
+    assert_eq!(written.as_object().unwrap().len(), expected.len());
+    for (team, rating) in expected {
+        assert!((written[&team].as_f64().unwrap() - rating as f64).abs() < 1e-3, "{}", team);
+    }
+}
+
+#[test]
+fn config_path_falls_back_to_the_environment() {
+    let dir = scratch_dir("config_env");
+    let config = write(&dir, "config.json", CONFIG);
+    let env_config = write(&dir, "other.json", r#"{"bo1_score": 1, "bo3_score": 1.25, "bo5_score": 1.5, "k_brackets": [{"start": 0, "k": 100}]}"#);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let from_flag = dir.join("from_flag.json").to_str().unwrap().to_string();
+    let from_env = dir.join("from_env.json").to_str().unwrap().to_string();
+    let flag_over_env = dir.join("flag_over_env.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &from_flag]).status().unwrap();
+    assert!(status.success());
+    let status = elo().env("ELO_CONFIG", &config).args(["update", "-s", &standings, "-m", &week_1, "-o", &from_env]).status().unwrap();
+    assert!(status.success());
+    let status = elo().env("ELO_CONFIG", &env_config).args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &flag_over_env]).status().unwrap();
+    assert!(status.success());
+
+    assert_eq!(read_standings(&from_env), read_standings(&from_flag));
+    assert_eq!(read_standings(&flag_over_env), read_standings(&from_flag));
+}
+
+#[test]
+fn previous_ratings_are_written_next_to_the_new_ones() {
+    let dir = scratch_dir("with_previous");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let week_2 = write(&dir, "week_2.json", WEEK_2);
+    let plain = dir.join("plain.json").to_str().unwrap().to_string();
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_2, "-o", &plain]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_2, "-o", &output, "--with-previous"]).status().unwrap();
+    assert!(status.success());
+
+    let plain = read_standings(&plain);
+    let changes = read_standings(&output);
+    assert_eq!(changes.as_object().unwrap().len(), 3);
+    assert_eq!(changes["TSM"]["old"], 500f64);
+    assert_eq!(changes["CLG"]["delta"], 0f64);
+    for (team, change) in changes.as_object().unwrap() {
+        let (old, new, delta) = (change["old"].as_f64().unwrap(), change["new"].as_f64().unwrap(), change["delta"].as_f64().unwrap());
+        assert!((old + delta - new).abs() < 1e-3, "{}", team);
+        assert_eq!(new, plain[team].as_f64().unwrap());
+    }
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_2, "--dry-run", "--with-previous", "--output-format", "csv"]).output().unwrap();
+    assert!(!status.status.success());
+}
+
+#[test]
+fn reset_ratings_starts_every_team_equal() {
+    let dir = scratch_dir("reset_ratings");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let other_standings = write(&dir, "other_standings.json", r#"{"C9": {"rating": 1800, "games": 40}, "TSM": 1200, "CLG": 1500}"#);
+    let week_1 = write(&dir, "week_1.json", WEEK_1);
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+    let other_output = dir.join("other_output.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &week_1, "-o", &output, "--reset-ratings"]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &other_standings, "-m", &week_1, "-o", &other_output, "--reset-ratings", "--with-uncertainty"]).status().unwrap();
+    assert!(status.success());
+
+    let ratings = read_standings(&output);
+    assert_eq!(ratings.as_object().unwrap().len(), 3);
+    assert_eq!(ratings["TSM"], 1500f64);
+    assert_eq!(ratings["CLG"].as_f64().unwrap() - 1500f64, 1500f64 - ratings["C9"].as_f64().unwrap());
+    let other = read_standings(&other_output);
+    for team in ["C9", "TSM", "CLG"] {
+        assert_eq!(other[team]["rating"], ratings[team], "{}", team);
+    }
+    assert_eq!(other["C9"]["games"], 1);
+}
+
+#[test]
+fn tune_prints_the_best_k_without_writing_standings() {
+    let dir = scratch_dir("tune");
+    let season = generate_season(12, 600, 2);
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", &serde_json::to_string(&season.standings).unwrap());
+    let matches: Vec<serde_json::Value> = season.matches.iter()
+        .map(|result| serde_json::json!({"winner": result.winner, "loser": result.loser, "series": result.series}))
+        .collect();
+    let matches = write(&dir, "matches.json", &serde_json::to_string(&matches).unwrap());
+
+    let output = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "--tune", "1,80"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let best_k: f64 = stdout.lines().find_map(|line| line.strip_prefix("Best k: ")).unwrap().parse().unwrap();
+    assert!(best_k > 1f64 && best_k < 80f64, "{}", stdout);
+    assert!(stdout.contains("Matches predicted: 600"));
+    assert!(stdout.contains("Log loss: "));
+
+    let output = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "--tune", "50,10"]).output().unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn strict_accepts_matches_that_use_an_alias() {
+    let dir = scratch_dir("strict_alias");
+    let config = write(&dir, "config.json", r#"{
+        "bo1_score": 1,
+        "bo3_score": 1.25,
+        "bo5_score": 1.5,
+        "k_brackets": [{"start": 0, "k": 32}],
+        "aliases": {"Cloud9": "C9"}
+    }"#);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let matches = write(&dir, "matches.json", r#"[{"winner": "Cloud9", "loser": "TSM", "series": "Bo1"}, {"winner": "CLG", "loser": "TSM", "series": "Bo1"}]"#);
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+
+    let run = elo().args(["update", "-c", &config, "-s", &standings, "-m", &matches, "-o", &output, "--strict"]).output().unwrap();
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert!(!String::from_utf8_lossy(&run.stderr).contains("warning"));
+
+    let ratings = read_standings(&output);
+    assert!(ratings.get("Cloud9").is_none());
+    assert!(ratings["C9"].as_f64().unwrap() > 950f64);
+}
+
+#[test]
+fn simulate_rejects_zero_iterations() {
+    let dir = scratch_dir("simulate_iterations");
+    let config = write(&dir, "config.json", CONFIG);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let schedule = write(&dir, "schedule.json", r#"[{"team_a": "C9", "team_b": "TSM"}, {"team_a": "TSM", "team_b": "CLG"}]"#);
+    let simulate = |iterations: &str| elo().args(["simulate", "-c", &config, "-s", &standings, "--schedule", &schedule, "-n", iterations]).output().unwrap();
+
+    let rejected = simulate("0");
+    assert!(!rejected.status.success());
+    assert!(String::from_utf8_lossy(&rejected.stderr).contains("invalid iteration count '0'"));
+    assert!(rejected.stdout.is_empty());
+
+    let simulated = simulate("50");
+    assert!(simulated.status.success());
+    let probabilities: serde_json::Value = serde_json::from_slice(&simulated.stdout).unwrap();
+    assert!(probabilities["C9"].as_array().unwrap().iter().all(|probability| probability.is_number()));
+}
+
+#[test]
+fn half_life_ignores_matches_that_are_never_rated() {
+    let dir = scratch_dir("half_life_ignored");
+    let config = write(&dir, "config.json", r#"{
+        "bo1_score": 1,
+        "bo3_score": 1.25,
+        "bo5_score": 1.5,
+        "k_brackets": [{"start": 0, "k": 32}],
+        "ignored_teams": ["BYE"]
+    }"#);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let rated = write(&dir, "rated.json", r#"[
+        {"winner": "CLG", "loser": "C9", "series": "Bo1"},
+        {"winner": "TSM", "loser": "C9", "series": "Bo3"}
+    ]"#);
+    let with_skipped = write(&dir, "with_skipped.json", r#"[
+        {"winner": "CLG", "loser": "C9", "series": "Bo1"},
+        {"winner": "C9", "loser": "BYE", "series": "Bo1"},
+        {"winner": "C9", "loser": "TSM", "series": "Bo1", "rated": false},
+        {"winner": "TSM", "loser": "C9", "series": "Bo3"},
+        {"winner": "BYE", "loser": "CLG", "series": "Bo1"}
+    ]"#);
+    let expected = dir.join("expected.json").to_str().unwrap().to_string();
+    let output = dir.join("output.json").to_str().unwrap().to_string();
+
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &rated, "-o", &expected, "--half-life", "1"]).status().unwrap();
+    assert!(status.success());
+    let status = elo().args(["update", "-c", &config, "-s", &standings, "-m", &with_skipped, "-o", &output, "--half-life", "1"]).status().unwrap();
+    assert!(status.success());
+
+    assert_eq!(read_standings(&output), read_standings(&expected));
+}
+
+#[test]
+fn predict_prints_win_chances_without_writing_standings() {
+    let dir = scratch_dir("predict");
+    let standings = write(&dir, "standings.json", STANDINGS);
+
+    let predicted = elo().args(["update", "-s", &standings, "--predict", "C9", "TSM"]).output().unwrap();
+    assert!(predicted.status.success());
+    let shown = elo().args(["show", "C9", "TSM", "-s", &standings]).output().unwrap();
+    assert_eq!(String::from_utf8(predicted.stdout).unwrap(), String::from_utf8(shown.stdout).unwrap());
+    assert_eq!(read_standings(&standings), serde_json::from_str::<serde_json::Value>(STANDINGS).unwrap());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let missing = elo().args(["update", "-s", &standings, "--predict", "C9", "FNC"]).output().unwrap();
+    assert!(!missing.status.success());
+    assert!(String::from_utf8(missing.stderr).unwrap().contains("FNC"));
+}
+
+#[test]
+fn bad_matches_are_handled_the_same_streamed_or_read_in_full() {
+    let dir = scratch_dir("ndjson_anomalies");
+    let config = write(&dir, "config.json", r#"{
+        "bo1_score": 1,
+        "bo3_score": 1.25,
+        "bo5_score": 1.5,
+        "k_brackets": [{"start": 0, "k": 32}],
+        "default_rating": 1000
+    }"#);
+    let standings = write(&dir, "standings.json", STANDINGS);
+    let lines = [
+        r#"{"winner": "CLG", "loser": "C9", "series": "Bo1"}"#,
+        r#"{"winner": "C9", "loser": "C9", "series": "Bo1"}"#,
+        r#"{"winner": "FNC", "loser": "CLG", "series": "Bo3"}"#,
+    ];
+    let json = write(&dir, "m.json", &format!("[{}]", lines.join(",")));
+    let ndjson = write(&dir, "m.ndjson", &lines.join("\n"));
+    let from_json = dir.join("from_json.json").to_str().unwrap().to_string();
+    let from_ndjson = dir.join("from_ndjson.json").to_str().unwrap().to_string();
+
+    let warnings = |matches: &str, output: &str| {
+        let run = elo().args(["update", "-c", &config, "-s", &standings, "-m", matches, "-o", output]).output().unwrap();
+        assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+        let mut warnings: Vec<String> = String::from_utf8(run.stderr).unwrap().lines().map(str::to_string).collect();
+        warnings.sort();
+        warnings
+    };
+    let json_warnings = warnings(&json, &from_json);
+    assert!(json_warnings.iter().any(|warning| warning.starts_with("warning: skipped match 1")));
+    assert!(json_warnings.iter().any(|warning| warning.contains("not in standings: FNC")));
+    assert_eq!(warnings(&ndjson, &from_ndjson), json_warnings);
+    assert_eq!(read_standings(&from_ndjson), read_standings(&from_json));
+}